@@ -0,0 +1,124 @@
+//! Compares the work-stealing [`ThreadPool`] with a pool in which all the
+//! workers share a single `Mutex<Receiver<Job>>`, which is how the
+//! [`ThreadPool`] was implemented before. Run it with `cargo bench`.
+
+use std::{
+    hint,
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use web_server::ThreadPool;
+
+const JOBS: usize = 200_000;
+const RUNS: u32 = 5;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct MutexReceiverPool {
+    sender: Option<Sender<Job>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl MutexReceiverPool {
+    fn build(size: NonZeroUsize) -> MutexReceiverPool {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let threads = (0..size.get())
+            .map(|_| {
+                let receiver: Arc<Mutex<Receiver<Job>>> = Arc::clone(&receiver);
+
+                thread::spawn(move || loop {
+                    let message = receiver.lock().unwrap().recv();
+
+                    match message {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+
+        MutexReceiverPool {
+            sender: Some(sender),
+            threads,
+        }
+    }
+
+    fn execute<F>(&self, f: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.sender.as_ref().unwrap().send(Box::new(f)).unwrap();
+    }
+}
+
+impl Drop for MutexReceiverPool {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+
+        for thread in self.threads.drain(..) {
+            thread.join().unwrap();
+        }
+    }
+}
+
+// runs JOBS short jobs and waits for all of them to finish
+fn run_short_jobs(execute: impl Fn(Job)) -> Duration {
+    let remaining = Arc::new(AtomicUsize::new(JOBS));
+    let (done_sender, done_receiver) = mpsc::channel();
+
+    let start = Instant::now();
+
+    for i in 0..JOBS {
+        let remaining = Arc::clone(&remaining);
+        let done_sender = done_sender.clone();
+
+        execute(Box::new(move || {
+            hint::black_box((0..32).fold(i, usize::wrapping_add));
+
+            if remaining.fetch_sub(1, Ordering::SeqCst) == 1 {
+                done_sender.send(()).unwrap();
+            }
+        }));
+    }
+
+    done_receiver.recv().unwrap();
+    start.elapsed()
+}
+
+fn best_of_runs(mut run: impl FnMut() -> Duration) -> Duration {
+    (0..RUNS)
+        .map(|_| run())
+        .min()
+        .expect("there should be at least one run")
+}
+
+fn main() {
+    println!("Executing {JOBS} short jobs, best of {RUNS} runs:");
+
+    for threads in [1usize, 2, 4, 8] {
+        let size = NonZeroUsize::new(threads).unwrap();
+
+        let mutex_receiver = best_of_runs(|| {
+            let pool = MutexReceiverPool::build(size);
+            run_short_jobs(|job| pool.execute(job))
+        });
+
+        let work_stealing = best_of_runs(|| {
+            let pool = ThreadPool::build(size);
+            run_short_jobs(|job| pool.execute(job))
+        });
+
+        println!(
+            "{threads} threads: Mutex<Receiver> {mutex_receiver:?}, work stealing {work_stealing:?}"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Logs the requests in the Combined Log Format, with the time taken to
+//! answer them in microseconds at the end of the line, like Apache's `%D`.
+
+use crate::http::{DateTime, Request, Response, Status};
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs::OpenOptions,
+    io::{self, Write},
+    net::IpAddr,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Where the [`Entry`] of every request is written to.
+pub struct AccessLog {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl AccessLog {
+    /// Opens the access log at `path`, appending to it if it exists,
+    /// or logs to stdout if `path` is `-`.
+    pub fn open(path: &Path) -> io::Result<AccessLog> {
+        let writer: Box<dyn Write + Send> = if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(OpenOptions::new().create(true).append(true).open(path)?)
+        };
+
+        Ok(AccessLog {
+            writer: Mutex::new(writer),
+        })
+    }
+
+    pub fn log(&self, entry: &Entry) -> io::Result<()> {
+        // written at once, so that the lines of concurrent requests do not mix
+        let line = format!("{entry}\n");
+
+        let mut writer = self
+            .writer
+            .lock()
+            .expect("access log lock should not be poisoned");
+
+        writer.write_all(line.as_bytes())?;
+        writer.flush()
+    }
+}
+
+/// What is logged about a request, displayed as a line of the access log.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    remote_address: Option<IpAddr>,
+    received_at: SystemTime,
+    started_at: Instant,
+
+    // `None` for requests that could not be read
+    request_line: Option<String>,
+
+    referer: Option<String>,
+    user_agent: Option<String>,
+    status: Status,
+    bytes_sent: u64,
+    duration: Duration,
+}
+
+impl Entry {
+    /// Starts the entry of a request that is being received now.
+    pub fn new(remote_address: Option<IpAddr>) -> Entry {
+        Entry {
+            remote_address,
+            received_at: SystemTime::now(),
+            started_at: Instant::now(),
+            request_line: None,
+            referer: None,
+            user_agent: None,
+            status: Status::Ok,
+            bytes_sent: 0,
+            duration: Duration::ZERO,
+        }
+    }
+
+    /// Fills in what is logged about the request itself.
+    pub fn describe_request(&mut self, request: &Request) {
+        let query = request
+            .query
+            .as_ref()
+            .map(|query| format!("?{query}"))
+            .unwrap_or_default();
+
+        self.request_line = Some(format!(
+            "{} {}{query} {}",
+            request.method, request.path, request.version
+        ));
+        self.referer = request.headers.get("Referer").map(ToString::to_string);
+        self.user_agent = request.headers.get("User-Agent").map(ToString::to_string);
+    }
+
+    /// Fills in what is logged about `response`, whose body was
+    /// sent if `sent` is `true`, and stops the clock.
+    pub fn finish(&mut self, response: &Response, sent: bool) {
+        self.status = response.status;
+        self.bytes_sent = if sent { response.body.length() } else { 0 };
+        self.duration = self.started_at.elapsed();
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.remote_address {
+            Some(address) => write!(f, "{address}")?,
+            None => f.write_str("-")?,
+        }
+
+        f.write_str(" - - [")?;
+        write_timestamp(f, self.received_at)?;
+        f.write_str("] ")?;
+        write_quoted(f, self.request_line.as_deref())?;
+        write!(f, " {} ", self.status.code())?;
+
+        if self.bytes_sent == 0 {
+            f.write_str("-")?;
+        } else {
+            write!(f, "{}", self.bytes_sent)?;
+        }
+
+        f.write_str(" ")?;
+        write_quoted(f, self.referer.as_deref())?;
+        f.write_str(" ")?;
+        write_quoted(f, self.user_agent.as_deref())?;
+        write!(f, " {}", self.duration.as_micros())
+    }
+}
+
+// like `10/Oct/2000:13:55:36 +0000`, always in UTC
+fn write_timestamp(f: &mut Formatter<'_>, time: SystemTime) -> fmt::Result {
+    let date = DateTime::new(time);
+
+    write!(
+        f,
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        date.day,
+        date.month_name(),
+        date.year,
+        date.hour,
+        date.minute,
+        date.second
+    )
+}
+
+// the fields come from the client, so they are escaped to keep the log parsable
+fn write_quoted(f: &mut Formatter<'_>, field: Option<&str>) -> fmt::Result {
+    let Some(field) = field else {
+        return f.write_str("\"-\"");
+    };
+
+    f.write_str("\"")?;
+
+    for byte in field.bytes() {
+        match byte {
+            b'"' | b'\\' => write!(f, "\\{}", char::from(byte))?,
+            b' '..=b'~' => write!(f, "{}", char::from(byte))?,
+            _ => write!(f, "\\x{byte:02x}")?,
+        }
+    }
+
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Limits;
+
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn formats_entries_in_the_combined_log_format() {
+        let input = "GET /search?q=rust HTTP/1.1\r\n\
+                     Host: localhost\r\n\
+                     User-Agent: curl/8.0 \"quoted\"\r\n\
+                     \r\n";
+
+        let request = Request::read_head(&mut input.as_bytes(), &Limits::default())
+            .unwrap()
+            .unwrap();
+
+        let mut entry = Entry::new(Some("192.168.1.7".parse().unwrap()));
+        entry.describe_request(&request);
+        entry.finish(&Response::new(Status::NotFound, "not found"), true);
+
+        entry.received_at = UNIX_EPOCH + Duration::from_secs(971_186_136);
+        entry.duration = Duration::from_micros(1534);
+
+        assert_eq!(
+            entry.to_string(),
+            "192.168.1.7 - - [10/Oct/2000:13:55:36 +0000] \"GET /search?q=rust HTTP/1.1\" \
+             404 9 \"-\" \"curl/8.0 \\\"quoted\\\"\" 1534",
+            "the entry is different"
+        );
+    }
+
+    #[test]
+    fn logs_unread_requests_with_dashes() {
+        let mut entry = Entry::new(None);
+        entry.finish(&Response::new(Status::BadRequest, "bad"), false);
+
+        entry.received_at = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        entry.duration = Duration::ZERO;
+
+        assert_eq!(
+            entry.to_string(),
+            "- - - [29/Feb/2000:00:00:00 +0000] \"-\" 400 - \"-\" \"-\" 0",
+            "the entry is different"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    logger::SharedLogger, thread_settings::ThreadSettings, Logger, PanicPolicy, ThreadPool,
+};
+
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+
+/// Configures and creates a [`ThreadPool`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::num::NonZeroUsize;
+/// use web_server::{PanicPolicy, ThreadPoolBuilder};
+///
+/// let pool = ThreadPoolBuilder::new(NonZeroUsize::new(4usize).unwrap())
+///     .panic_policy(PanicPolicy::CatchAndContinue)
+///     .build();
+///
+/// pool.execute(|| panic!("this does not bring the server down"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThreadPoolBuilder {
+    pub(crate) size: NonZeroUsize,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) queue_capacity: Option<usize>,
+    pub(crate) max_size: NonZeroUsize,
+    pub(crate) idle_timeout: Duration,
+    pub(crate) logger: SharedLogger,
+    pub(crate) thread_settings: ThreadSettings,
+}
+
+impl ThreadPoolBuilder {
+    /// Creates a new [`ThreadPoolBuilder`] for a pool
+    /// with `size` threads.
+    ///
+    /// The pool keeps that many threads unless it is given a
+    /// [`ThreadPoolBuilder::max_size`] or resized with [`ThreadPool::set_size`].
+    #[must_use]
+    pub fn new(size: NonZeroUsize) -> ThreadPoolBuilder {
+        ThreadPoolBuilder {
+            size,
+            panic_policy: PanicPolicy::default(),
+            queue_capacity: None,
+            max_size: size,
+            idle_timeout: Duration::from_secs(60),
+            logger: SharedLogger::default(),
+            thread_settings: ThreadSettings::default(),
+        }
+    }
+
+    /// Sets what happens when a job panics. Defaults to [`PanicPolicy::Abort`].
+    #[must_use]
+    pub fn panic_policy(mut self, panic_policy: PanicPolicy) -> ThreadPoolBuilder {
+        self.panic_policy = panic_policy;
+        self
+    }
+
+    /// Limits the number of jobs that can wait in the queue for a free thread.
+    /// By default, the queue is unbounded.
+    ///
+    /// When the queue is full, [`ThreadPool::execute`] blocks,
+    /// [`ThreadPool::try_execute`] fails right away and
+    /// [`ThreadPool::execute_timeout`] fails after a timeout.
+    /// A capacity of zero means that a job is accepted only
+    /// when a thread is ready to execute it.
+    #[must_use]
+    pub fn queue_capacity(mut self, capacity: usize) -> ThreadPoolBuilder {
+        self.queue_capacity = Some(capacity);
+        self
+    }
+
+    /// Lets the pool grow up to `max_size` threads, spawning a new thread
+    /// whenever a job is given to the pool while all of its threads are busy.
+    ///
+    /// The pool starts with the size given to [`ThreadPoolBuilder::new`] and
+    /// never shrinks below it. Threads that stay idle for longer than the
+    /// [`ThreadPoolBuilder::idle_timeout`] exit. A `max_size` smaller than the
+    /// starting size is ignored.
+    #[must_use]
+    pub fn max_size(mut self, max_size: NonZeroUsize) -> ThreadPoolBuilder {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets how long a thread above the starting size can stay idle before
+    /// exiting, see [`ThreadPoolBuilder::max_size`]. Defaults to one minute.
+    #[must_use]
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> ThreadPoolBuilder {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Gives the events of the pool to `logger`, see [`PoolEvent`](crate::PoolEvent).
+    /// By default, the events are not logged anywhere.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::num::NonZeroUsize;
+    /// use web_server::{LogLevel, StderrLogger, ThreadPoolBuilder};
+    ///
+    /// let pool = ThreadPoolBuilder::new(NonZeroUsize::new(4usize).unwrap())
+    ///     .logger(StderrLogger::new(LogLevel::Info))
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn logger(mut self, logger: impl Logger + 'static) -> ThreadPoolBuilder {
+        self.logger = SharedLogger::new(logger);
+        self
+    }
+
+    /// Names the threads of the pool `prefix` followed by the id of
+    /// their worker, for example `web-worker-3` for a `web-worker-` prefix.
+    /// By default, the threads are unnamed.
+    #[must_use]
+    pub fn thread_name_prefix(mut self, prefix: impl Into<String>) -> ThreadPoolBuilder {
+        self.thread_settings.name_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets the stack size of the threads of the pool, in bytes.
+    /// By default, the threads get the stack size of [`std::thread::spawn`].
+    #[must_use]
+    pub fn stack_size(mut self, stack_size: usize) -> ThreadPoolBuilder {
+        self.thread_settings.stack_size = Some(stack_size);
+        self
+    }
+
+    /// Calls `on_start` with the id of the worker on every new thread of the
+    /// pool, before the thread executes any job. Useful for setting up
+    /// thread-locals.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{cell::Cell, num::NonZeroUsize};
+    /// use web_server::ThreadPoolBuilder;
+    ///
+    /// thread_local! {
+    ///     static WORKER_ID: Cell<Option<usize>> = const { Cell::new(None) };
+    /// }
+    ///
+    /// let pool = ThreadPoolBuilder::new(NonZeroUsize::new(4usize).unwrap())
+    ///     .on_thread_start(|worker_id| WORKER_ID.set(Some(worker_id)))
+    ///     .on_thread_stop(|worker_id| println!("Worker {worker_id} stopped."))
+    ///     .build();
+    ///
+    /// pool.execute(|| println!("Running on worker {:?}.", WORKER_ID.get()));
+    /// ```
+    #[must_use]
+    pub fn on_thread_start<F>(mut self, on_start: F) -> ThreadPoolBuilder
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        self.thread_settings.on_start = Some(Arc::new(on_start));
+        self
+    }
+
+    /// Calls `on_stop` with the id of the worker on every thread of
+    /// the pool that is about to exit, including threads that
+    /// exit because of a panic.
+    #[must_use]
+    pub fn on_thread_stop<F>(mut self, on_stop: F) -> ThreadPoolBuilder
+    where
+        F: Fn(usize) + Send + Sync + 'static,
+    {
+        self.thread_settings.on_stop = Some(Arc::new(on_stop));
+        self
+    }
+
+    /// Creates the [`ThreadPool`] with the current configuration.
+    #[must_use]
+    pub fn build(self) -> ThreadPool {
+        ThreadPool::from_builder(&self)
+    }
+}
@@ -0,0 +1,31 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Revokes the queued jobs it was given to, see
+/// [`ThreadPool::execute_cancellable`](crate::ThreadPool::execute_cancellable).
+///
+/// Clones of a token share its state, so cancelling
+/// any of them cancels all of them.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    #[must_use]
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Makes sure that the jobs given this token are not executed if no
+    /// thread has picked them up yet. Jobs that are already executing
+    /// are not interrupted.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,322 @@
+//! Compresses the responses with the coding the client
+//! prefers among the ones `Accept-Encoding` allows.
+
+mod deflate;
+
+use crate::http::{Body, Response, Status};
+
+use std::io::{self, Read};
+
+// larger bodies are streamed as they are. Compressed bodies are not cached,
+// not even the ones of files kept in the `FileCache`, so this also bounds
+// the time a worker spends compressing for every request, not just the first
+// one for a file.
+const MAX_LENGTH: u64 = 1024 * 1024;
+
+/// Which responses are compressed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Compression {
+    /// The smallest body worth compressing, in bytes.
+    pub min_size: u64,
+
+    /// The media types of the bodies worth compressing, in lowercase
+    /// and without parameters. Nothing is compressed if it is empty.
+    pub types: Vec<String>,
+}
+
+impl Default for Compression {
+    fn default() -> Compression {
+        let types = [
+            "text/html",
+            "text/css",
+            "text/plain",
+            "text/javascript",
+            "text/xml",
+            "application/json",
+            "application/xml",
+            "image/svg+xml",
+        ];
+
+        Compression {
+            min_size: 1024,
+            types: types.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl Compression {
+    /// Compresses the body of `response` if it is eligible and
+    /// `accept_encoding`, the header of the request, allows it.
+    ///
+    /// The entity tag of a compressed response is made weak, since
+    /// its bytes are not the ones the tag was made for.
+    ///
+    /// The responses of a type worth compressing vary on `Accept-Encoding`
+    /// even if they are not compressed, so that caches do not give them to
+    /// clients that could have got them compressed, or the other way around.
+    /// This includes the 304s, which stand for the 200s of the same type.
+    ///
+    /// Nothing is kept between calls: a body shared by the file cache is
+    /// compressed again for every response it is sent in.
+    pub fn compress(
+        &self,
+        accept_encoding: Option<&str>,
+        response: &mut Response,
+    ) -> io::Result<()> {
+        if !self.varies(response) {
+            return Ok(());
+        }
+
+        response.headers.add("Vary", "Accept-Encoding");
+
+        let length = response.body.length();
+
+        // a 304 has no body, even if the 200 it stands for would be compressed
+        if response.status == Status::NotModified
+            || length == 0
+            || length < self.min_size
+            || length > MAX_LENGTH
+        {
+            return Ok(());
+        }
+
+        let Some(coding) = accept_encoding.and_then(negotiate) else {
+            return Ok(());
+        };
+
+        // the contents of a file, which are no longer in it once read
+        let mut read = None;
+
+        let body: &[u8] = match &mut response.body {
+            Body::Bytes(bytes) => bytes,
+            Body::Shared(bytes) => bytes,
+            Body::File { file, length } => {
+                let mut bytes = Vec::new();
+                file.take(*length).read_to_end(&mut bytes)?;
+                read.insert(bytes)
+            }
+            // only partial responses have them, and they are not compressed
+            Body::FileRanges { .. } => return Ok(()),
+        };
+
+        let compressed = coding.encode(body);
+
+        // only for incompressible data, which the types should rule out
+        if compressed.len() < body.len() {
+            response.headers.add("Content-Encoding", coding.name());
+
+            if let Some(etag) = response.headers.get("ETag") {
+                if !etag.starts_with("W/") {
+                    response.headers.set("ETag", format!("W/{etag}"));
+                }
+            }
+
+            response.body = Body::Bytes(compressed);
+        } else if let Some(read) = read {
+            response.body = Body::Bytes(read);
+        }
+
+        Ok(())
+    }
+
+    // whether the response could be compressed, whatever its size
+    fn varies(&self, response: &Response) -> bool {
+        if response.headers.contains("Content-Encoding")
+            // the ranges are of the uncompressed body
+            || response.status == Status::PartialContent
+        {
+            return false;
+        }
+
+        response
+            .headers
+            .get("Content-Type")
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|media_type| media_type.trim().to_ascii_lowercase())
+            .is_some_and(|media_type| self.types.contains(&media_type))
+    }
+}
+
+/// The content codings responses can be compressed with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Coding {
+    Gzip,
+    Deflate,
+}
+
+impl Coding {
+    fn name(self) -> &'static str {
+        match self {
+            Coding::Gzip => "gzip",
+            Coding::Deflate => "deflate",
+        }
+    }
+
+    fn encode(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Coding::Gzip => deflate::gzip(data),
+            Coding::Deflate => deflate::zlib(data),
+        }
+    }
+}
+
+// the coding with the highest quality, gzip if both have the same one
+fn negotiate(accept_encoding: &str) -> Option<Coding> {
+    let mut gzip = None;
+    let mut deflate = None;
+    let mut any = None;
+
+    for element in accept_encoding.split(',') {
+        let element = element.to_ascii_lowercase();
+        let mut parts = element.split(';').map(str::trim);
+
+        let coding = parts.next().unwrap_or_default();
+
+        let quality = match parts.find_map(|parameter| parameter.strip_prefix("q=")) {
+            Some(quality) => match parse_quality(quality) {
+                Some(quality) => quality,
+                None => continue,
+            },
+            None => 1000,
+        };
+
+        match coding {
+            "gzip" | "x-gzip" => gzip = Some(quality),
+            "deflate" => deflate = Some(quality),
+            "*" => any = Some(quality),
+            _ => {}
+        }
+    }
+
+    // `*` stands for the codings that are not listed
+    let gzip = gzip.or(any).unwrap_or(0);
+    let deflate = deflate.or(any).unwrap_or(0);
+
+    if gzip == 0 && deflate == 0 {
+        None
+    } else if gzip >= deflate {
+        Some(Coding::Gzip)
+    } else {
+        Some(Coding::Deflate)
+    }
+}
+
+// in thousandths, so that qualities can be compared exactly
+fn parse_quality(quality: &str) -> Option<u16> {
+    let (units, decimals) = quality.split_once('.').unwrap_or((quality, ""));
+
+    if decimals.len() > 3 || !decimals.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let thousandths: u16 = format!("{decimals:0<3}").parse().ok()?;
+
+    match units {
+        "0" => Some(thousandths),
+        "1" if thousandths == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn html_response(length: usize) -> Response {
+        let mut response = Response::new(Status::Ok, "<p>hello</p>".repeat(length / 12));
+        response
+            .headers
+            .add("Content-Type", "text/html; charset=utf-8");
+
+        response
+    }
+
+    #[test]
+    fn prefers_the_coding_with_the_highest_quality() {
+        let cases = [
+            ("gzip, deflate, br", Some(Coding::Gzip)),
+            ("deflate, gzip;q=0.5", Some(Coding::Deflate)),
+            ("gzip;q=0, *", Some(Coding::Deflate)),
+            ("GZIP;Q=0.8, deflate;q=0.75", Some(Coding::Gzip)),
+            ("*;q=0, identity", None),
+            ("br, gzip;q=2", None),
+            ("", None),
+        ];
+
+        for (accept_encoding, coding) in cases {
+            assert_eq!(
+                negotiate(accept_encoding),
+                coding,
+                "{accept_encoding:?} was not negotiated correctly"
+            );
+        }
+    }
+
+    #[test]
+    fn compresses_and_varies_the_responses_of_the_listed_types_only() {
+        let compression = Compression::default();
+
+        let mut response = html_response(2048);
+        compression.compress(Some("gzip"), &mut response).unwrap();
+
+        assert_eq!(
+            response.headers.get("Content-Encoding"),
+            Some("gzip"),
+            "the response was not compressed"
+        );
+        assert_eq!(
+            response.headers.get("Vary"),
+            Some("Accept-Encoding"),
+            "the compressed response does not vary"
+        );
+        assert!(
+            matches!(&response.body, Body::Bytes(body) if body.starts_with(&[0x1f, 0x8b])),
+            "the body is not gzipped"
+        );
+
+        let mut response = html_response(2048);
+        compression.compress(None, &mut response).unwrap();
+
+        assert!(
+            !response.headers.contains("Content-Encoding"),
+            "the response was compressed without being accepted"
+        );
+        assert_eq!(
+            response.headers.get("Vary"),
+            Some("Accept-Encoding"),
+            "the uncompressed response does not vary"
+        );
+
+        let mut response = html_response(512);
+        compression.compress(Some("gzip"), &mut response).unwrap();
+
+        assert!(
+            !response.headers.contains("Content-Encoding"),
+            "a response under the threshold was compressed"
+        );
+        assert_eq!(
+            response.headers.get("Vary"),
+            Some("Accept-Encoding"),
+            "a response under the threshold does not vary with the larger ones"
+        );
+
+        let mut response = Response::new(Status::NotModified, Vec::new());
+        response.headers.add("Content-Type", "text/html");
+        compression.compress(Some("gzip"), &mut response).unwrap();
+
+        assert_eq!(
+            response.headers.get("Vary"),
+            Some("Accept-Encoding"),
+            "a 304 does not vary like the 200 it stands for"
+        );
+
+        let mut response = Response::new(Status::Ok, vec![0; 4096]);
+        response.headers.add("Content-Type", "image/png");
+        compression.compress(Some("gzip"), &mut response).unwrap();
+
+        assert!(
+            response.headers.get("Vary").is_none(),
+            "a response of an excluded type varies"
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! A DEFLATE encoder (RFC 1951) and the gzip (RFC 1952) and zlib
+//! (RFC 1950) formats around it, which std does not provide.
+//!
+//! The data is compressed with LZ77 and the fixed Huffman codes in a
+//! single block, which is simpler than building codes for every response
+//! and still shrinks text a lot.
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+// how many earlier positions with the same hash are tried for a match
+const MAX_CHAIN: usize = 64;
+
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const END_OF_BLOCK: u16 = 256;
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+/// Compresses `data` in the gzip format.
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    // no file name or modification time, and an unknown operating system
+    let mut output = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255];
+    output.extend(deflate(data));
+    output.extend(crc32(data).to_le_bytes());
+
+    // the length modulo 2^32, as the format wants it
+    #[allow(clippy::cast_possible_truncation)]
+    output.extend((data.len() as u32).to_le_bytes());
+
+    output
+}
+
+/// Compresses `data` in the zlib format, which is what
+/// the `deflate` content coding of HTTP means.
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    // a 32 KiB window, and a header checksum making it a multiple of 31
+    let mut output = vec![0x78, 0x01];
+    output.extend(deflate(data));
+    output.extend(adler32(data).to_be_bytes());
+    output
+}
+
+/// Compresses `data` in the raw DEFLATE format.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::default();
+    let mut matcher = Matcher::new(data);
+
+    // the final block, compressed with the fixed codes
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+
+    let mut position = 0;
+
+    while position < data.len() {
+        if let Some((length, distance)) = matcher.longest_match(position) {
+            writer.write_match(length, distance);
+
+            for inserted in position..position + length {
+                matcher.insert(inserted);
+            }
+
+            position += length;
+        } else {
+            writer.write_literal_or_length(u16::from(data[position]));
+            matcher.insert(position);
+            position += 1;
+        }
+    }
+
+    writer.write_literal_or_length(END_OF_BLOCK);
+    writer.finish()
+}
+
+// finds earlier occurrences of the data at a position through chains of
+// positions whose next bytes have the same hash
+struct Matcher<'a> {
+    data: &'a [u8],
+
+    // the last position with a given hash
+    heads: Vec<Option<usize>>,
+
+    // the position before a given one with the same hash, by position in the window
+    previous: Vec<Option<usize>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(data: &'a [u8]) -> Matcher<'a> {
+        Matcher {
+            data,
+            heads: vec![None; 1 << HASH_BITS],
+            previous: vec![None; WINDOW_SIZE],
+        }
+    }
+
+    fn hash(&self, position: usize) -> Option<usize> {
+        let bytes = self.data.get(position..position + MIN_MATCH)?;
+        let hash =
+            (usize::from(bytes[0]) << 10) ^ (usize::from(bytes[1]) << 5) ^ usize::from(bytes[2]);
+
+        Some(hash & ((1 << HASH_BITS) - 1))
+    }
+
+    fn insert(&mut self, position: usize) {
+        if let Some(hash) = self.hash(position) {
+            self.previous[position % WINDOW_SIZE] = self.heads[hash];
+            self.heads[hash] = Some(position);
+        }
+    }
+
+    // the length and distance of the longest match, if it is long enough
+    fn longest_match(&self, position: usize) -> Option<(usize, usize)> {
+        let max_length = MAX_MATCH.min(self.data.len() - position);
+        let mut candidate = self.heads[self.hash(position)?];
+        let mut best: Option<(usize, usize)> = None;
+
+        for _ in 0..MAX_CHAIN {
+            let Some(earlier) = candidate else {
+                break;
+            };
+
+            let distance = position - earlier;
+
+            // older positions may have been overwritten in `previous`
+            if distance > WINDOW_SIZE {
+                break;
+            }
+
+            let length = self.data[earlier..]
+                .iter()
+                .zip(&self.data[position..position + max_length])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if length >= MIN_MATCH && best.map_or(true, |(best_length, _)| length > best_length) {
+                best = Some((length, distance));
+
+                if length == max_length {
+                    break;
+                }
+            }
+
+            candidate = self.previous[earlier % WINDOW_SIZE];
+        }
+
+        best
+    }
+}
+
+// packs bits starting from the least significant bit of every byte
+#[derive(Default)]
+struct BitWriter {
+    output: Vec<u8>,
+    buffer: u32,
+    buffered_bits: u32,
+}
+
+impl BitWriter {
+    fn write_bits(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.buffered_bits;
+        self.buffered_bits += count;
+
+        while self.buffered_bits >= 8 {
+            self.output.push(self.buffer.to_le_bytes()[0]);
+            self.buffer >>= 8;
+            self.buffered_bits -= 8;
+        }
+    }
+
+    // Huffman codes start from their most significant bit
+    fn write_code(&mut self, code: u16, length: u32) {
+        let reversed = u32::from(code.reverse_bits()) >> (16 - length);
+        self.write_bits(reversed, length);
+    }
+
+    fn write_literal_or_length(&mut self, symbol: u16) {
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn write_match(&mut self, length: usize, distance: usize) {
+        let (index, extra) = symbol_index(&LENGTH_BASES, length);
+        self.write_literal_or_length(257 + index);
+        self.write_bits(extra, LENGTH_EXTRA_BITS[usize::from(index)].into());
+
+        let (index, extra) = symbol_index(&DISTANCE_BASES, distance);
+        self.write_code(index, 5);
+        self.write_bits(extra, DISTANCE_EXTRA_BITS[usize::from(index)].into());
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.buffered_bits > 0 {
+            self.write_bits(0, 8 - self.buffered_bits);
+        }
+
+        self.output
+    }
+}
+
+// the index of the symbol whose range holds `value`, and the offset of `value` in it
+fn symbol_index(bases: &[u16], value: usize) -> (u16, u32) {
+    let index = bases.partition_point(|&base| usize::from(base) <= value) - 1;
+    let extra = value - usize::from(bases[index]);
+
+    (
+        u16::try_from(index).expect("there should be less than 2^16 symbols"),
+        u32::try_from(extra).expect("the offset should fit in the extra bits"),
+    )
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut byte = 0;
+
+    while byte < 256 {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut crc = byte as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[byte] = crc;
+        byte += 1;
+    }
+
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[usize::from(crc.to_le_bytes()[0] ^ byte)] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65_521;
+
+    let (a, b) = data.iter().fold((1, 0), |(a, b), &byte| {
+        let a = (a + u32::from(byte)) % MODULUS;
+        (a, (b + a) % MODULUS)
+    });
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_the_checksums_of_the_formats() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926, "the CRC-32 is different");
+        assert_eq!(
+            adler32(b"Wikipedia"),
+            0x11e6_0398,
+            "the Adler-32 is different"
+        );
+    }
+
+    #[test]
+    fn compresses_repeated_data_with_matches() {
+        assert_eq!(
+            zlib(b"hello hello hello hello"),
+            [120, 1, 203, 72, 205, 201, 201, 87, 192, 32, 1, 104, 3, 8, 177],
+            "the compressed data is different"
+        );
+
+        let html = "<li class=\"item\">An item</li>\n".repeat(1000);
+        let compressed = gzip(html.as_bytes());
+
+        assert!(
+            compressed.len() < html.len() / 20,
+            "the data was compressed to {} bytes",
+            compressed.len()
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use crate::{compression::Compression, http::Limits};
+use toml::{Table, Value};
+use web_server::LogLevel;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::NonZeroUsize,
+    path::PathBuf,
+    time::Duration,
+};
+
+pub const USAGE: &str = "\
+Usage: web-server [OPTIONS]
+
+Options:
+  --config <FILE>              Reads the options from a TOML file, flags override it
+  --address <IP>               The address to listen on [default: 127.0.0.1]
+  --port <PORT>                The port to listen on, 0 for any free one [default: 7878]
+  --workers <COUNT>            The number of worker threads [default: 4]
+  --queue-capacity <COUNT>     How many connections can wait for a busy worker [default: 16]
+  --keep-alive-timeout <SECS>  How long an idle connection stays open [default: 5]
+  --header-timeout <SECS>      How long reading the request line and header fields can take [default: 5]
+  --request-timeout <SECS>     How long reading the body and writing the response can take [default: 5]
+  --max-request-line <BYTES>   The longest request line, longer ones are answered with 414 [default: 8192]
+  --max-headers <COUNT>        The most header fields, more are answered with 431 [default: 100]
+  --max-headers-size <BYTES>   The most bytes of header fields, more are answered with 431 [default: 16384]
+  --max-body-size <BYTES>      The largest request body, larger ones are answered with 413 [default: 1048576]
+  --document-root <DIR>        The directory the static files are served from [default: res]
+  --max-connections <COUNT>    How many connections can be open at once, the others get a 503 [default: the workers plus the queue capacity]
+  --retry-after <SECS>         How long the clients getting a 503 are asked to wait [default: 1]
+  --shutdown-timeout <SECS>    How long the open connections can take to finish on SIGINT or SIGTERM [default: 10]
+  --access-log <FILE>          Where to log the requests, '-' for stdout [default: none]
+  --pool-log-level <LEVEL>     The least important events of the worker threads logged to stderr:
+                               off, error, warn, info or debug [default: off]
+  --compression-min-size <BYTES>
+                               The smallest body compressed with gzip or deflate [default: 1024]
+  --compression-types <TYPES>  The comma-separated media types compressed [default: text/html,
+                               text/css, text/plain, text/javascript, text/xml, application/json,
+                               application/xml, image/svg+xml]
+  --file-cache-size <BYTES>    How much of the served files can be kept in memory [default: none]
+  --cache-control <ROUTE=VALUE>
+                               The Cache-Control of the successful responses of a route, like
+                               '/static/*path=max-age=3600', can be repeated
+  -h, --help                   Prints this help
+
+The options of the file have the names of the flags, with '_' instead of '-'.
+
+In the file, `compression_types` can be an array of strings. Nothing is
+compressed if it is empty.
+
+In the file, `cache_control` is a table whose keys are the routes.
+
+The requests are logged in the Combined Log Format, followed by the time
+taken to answer them in microseconds.
+
+The server exits with a failure if some connections did not finish before the
+shutdown timeout. A second SIGINT or SIGTERM makes it exit right away.
+";
+
+/// How the web server is set up, see [`USAGE`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub address: IpAddr,
+    pub port: u16,
+    pub workers: NonZeroUsize,
+
+    /// How many connections can wait for a worker, beyond the ones being handled.
+    pub queue_capacity: usize,
+    pub keep_alive_timeout: Duration,
+    pub header_timeout: Duration,
+    pub request_timeout: Duration,
+    pub limits: Limits,
+    pub document_root: PathBuf,
+
+    /// `None` for as many as the workers and their queue can take,
+    /// see [`Config::max_connections`].
+    pub max_connections: Option<NonZeroUsize>,
+    pub retry_after: Duration,
+    pub shutdown_timeout: Duration,
+    pub access_log: Option<PathBuf>,
+
+    /// `None` for no log of the thread pool.
+    pub pool_log_level: Option<LogLevel>,
+    pub compression: Compression,
+    pub file_cache_size: Option<u64>,
+
+    /// The `Cache-Control` of the routes, by pattern.
+    pub cache_control: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 7878,
+            workers: NonZeroUsize::new(4).unwrap(),
+            queue_capacity: 16,
+            keep_alive_timeout: Duration::from_secs(5),
+            header_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(5),
+            limits: Limits::default(),
+            document_root: PathBuf::from("res"),
+            max_connections: None,
+            retry_after: Duration::from_secs(1),
+            shutdown_timeout: Duration::from_secs(10),
+            access_log: None,
+            pool_log_level: None,
+            compression: Compression::default(),
+            file_cache_size: None,
+            cache_control: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration from the command-line arguments,
+    /// without the name of the program. The options are read from the
+    /// defaults, then from the `--config` file, then from the flags.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
+        let mut args = args.into_iter();
+        let mut config_file = None;
+        let mut flags = Vec::new();
+
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(format!("unexpected argument `{arg}`"));
+            };
+
+            let (name, value) = if let Some((name, value)) = flag.split_once('=') {
+                (name.to_string(), value.to_string())
+            } else {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("`--{flag}` needs a value"))?;
+
+                (flag.to_string(), value)
+            };
+
+            if name == "config" {
+                config_file = Some(value);
+            } else {
+                flags.push((name, value));
+            }
+        }
+
+        let mut config = Config::default();
+
+        if let Some(path) = config_file {
+            let document =
+                fs::read_to_string(&path).map_err(|e| format!("could not read {path}: {e}"))?;
+
+            let table = document
+                .parse::<Table>()
+                .map_err(|e| format!("{path}, {}", describe_toml_error(&document, &e)))?;
+
+            for (key, value) in &table {
+                config
+                    .set(key, value)
+                    .map_err(|e| format!("{path}, `{key}` {e}"))?;
+            }
+        }
+
+        for (name, value) in flags {
+            config
+                .set(&name.replace('-', "_"), &Value::String(value))
+                .map_err(|e| format!("`--{name}` {e}"))?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn socket_address(&self) -> SocketAddr {
+        SocketAddr::new(self.address, self.port)
+    }
+
+    /// How many connections can be open at once. By default, the idle
+    /// keep-alive connections holding the workers make the others wait
+    /// in the queue instead of being shed.
+    pub fn max_connections(&self) -> usize {
+        self.max_connections.map_or(
+            self.workers.get().saturating_add(self.queue_capacity),
+            NonZeroUsize::get,
+        )
+    }
+
+    // the flags give every value as a string, so strings
+    // are accepted wherever another type is expected
+    fn set(&mut self, key: &str, value: &Value) -> Result<(), String> {
+        match key {
+            "address" => {
+                self.address = string(value)?
+                    .parse()
+                    .map_err(|_| format!("should be an IP address, not {}", describe(value)))?;
+            }
+            "port" => {
+                self.port = integer(value)
+                    .and_then(|port| u16::try_from(port).ok())
+                    .ok_or_else(|| format!("should be a port number, not {}", describe(value)))?;
+            }
+            "workers" => self.workers = positive_integer(value)?,
+            "queue_capacity" => self.queue_capacity = count(value)?,
+            "keep_alive_timeout" => self.keep_alive_timeout = seconds(value)?,
+            "header_timeout" => self.header_timeout = seconds(value)?,
+            "request_timeout" => self.request_timeout = seconds(value)?,
+            "max_request_line" => {
+                self.limits.request_line_length = positive_integer(value)?.get();
+            }
+            "max_headers" => self.limits.header_count = positive_integer(value)?.get(),
+            "max_headers_size" => self.limits.headers_size = positive_integer(value)?.get(),
+            "max_body_size" => self.limits.body_size = bytes(value)?,
+            "document_root" => self.document_root = PathBuf::from(string(value)?),
+            "max_connections" => self.max_connections = Some(positive_integer(value)?),
+            "retry_after" => self.retry_after = seconds(value)?,
+            "shutdown_timeout" => self.shutdown_timeout = seconds(value)?,
+            "access_log" => self.access_log = Some(PathBuf::from(string(value)?)),
+            "pool_log_level" => self.pool_log_level = log_level(value)?,
+            "compression_min_size" => self.compression.min_size = bytes(value)?,
+            "compression_types" => {
+                self.compression.types = strings(value)?
+                    .into_iter()
+                    .map(|media_type| media_type.to_ascii_lowercase())
+                    .collect();
+            }
+            "file_cache_size" => self.file_cache_size = Some(bytes(value)?),
+            "cache_control" => match value {
+                // a flag sets a single route, and can be repeated
+                Value::String(setting) => {
+                    let (pattern, value) = setting
+                        .split_once('=')
+                        .ok_or_else(|| format!("should be like ROUTE=VALUE, not `{setting}`"))?;
+
+                    self.cache_control
+                        .insert(pattern.to_string(), value.to_string());
+                }
+                Value::Table(table) => {
+                    for (pattern, value) in table {
+                        let value = string(value).map_err(|e| format!("`{pattern}` {e}"))?;
+
+                        self.cache_control
+                            .insert(pattern.clone(), value.to_string());
+                    }
+                }
+                _ => return Err(format!("should be a table, not {}", type_name(value))),
+            },
+            _ => return Err("is not an option".to_string()),
+        }
+
+        Ok(())
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("`{s}`"),
+        Value::Integer(i) => format!("`{i}`"),
+        _ => type_name(value).to_string(),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "a string",
+        Value::Integer(_) => "an integer",
+        Value::Float(_) => "a float",
+        Value::Boolean(_) => "a boolean",
+        Value::Datetime(_) => "a date",
+        Value::Array(_) => "an array",
+        Value::Table(_) => "a table",
+    }
+}
+
+// on a single line, unlike the errors of the parser, which quote the document
+fn describe_toml_error(document: &str, error: &toml::de::Error) -> String {
+    let message = error.message().trim_end().replace('\n', ", ");
+
+    match error.span() {
+        Some(span) => {
+            let line = document[..span.start].matches('\n').count() + 1;
+            format!("line {line}: {message}")
+        }
+        None => message,
+    }
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(format!("should be a string, not {}", type_name(value))),
+    }
+}
+
+// a flag gives the strings separated by commas
+fn strings(value: &Value) -> Result<Vec<String>, String> {
+    match value {
+        Value::String(s) => Ok(s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(ToString::to_string)
+            .collect()),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| string(value).map(ToString::to_string))
+            .collect(),
+        _ => Err(format!(
+            "should be an array of strings, not {}",
+            type_name(value)
+        )),
+    }
+}
+
+fn integer(value: &Value) -> Option<i64> {
+    match value {
+        Value::Integer(i) => Some(*i),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn count(value: &Value) -> Result<usize, String> {
+    integer(value)
+        .and_then(|i| usize::try_from(i).ok())
+        .ok_or_else(|| format!("should be a non-negative integer, not {}", describe(value)))
+}
+
+fn positive_integer(value: &Value) -> Result<NonZeroUsize, String> {
+    integer(value)
+        .and_then(|i| usize::try_from(i).ok())
+        .and_then(NonZeroUsize::new)
+        .ok_or_else(|| format!("should be a positive integer, not {}", describe(value)))
+}
+
+fn bytes(value: &Value) -> Result<u64, String> {
+    integer(value)
+        .and_then(|i| u64::try_from(i).ok())
+        .ok_or_else(|| format!("should be a number of bytes, not {}", describe(value)))
+}
+
+fn log_level(value: &Value) -> Result<Option<LogLevel>, String> {
+    match string(value)?.to_ascii_lowercase().as_str() {
+        "off" => Ok(None),
+        "error" => Ok(Some(LogLevel::Error)),
+        "warn" => Ok(Some(LogLevel::Warn)),
+        "info" => Ok(Some(LogLevel::Info)),
+        "debug" => Ok(Some(LogLevel::Debug)),
+        _ => Err(format!(
+            "should be off, error, warn, info or debug, not {}",
+            describe(value)
+        )),
+    }
+}
+
+fn seconds(value: &Value) -> Result<Duration, String> {
+    integer(value)
+        .and_then(|i| u64::try_from(i).ok())
+        .filter(|&seconds| seconds > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(|| {
+            format!(
+                "should be a positive number of seconds, not {}",
+                describe(value)
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, process};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn flags_override_the_config_file() {
+        let path = env::temp_dir().join(format!("web-server-{}-config.toml", process::id()));
+
+        fs::write(
+            &path,
+            "address = '0.0.0.0'\nport = 8080\nworkers = 8\nmax_connections = 100\n\
+             compression_types = [\n  \"Text/HTML\",\n  'application/json',\n]\n\
+             cache_control = { \"/\" = \"no-cache\" }\n",
+        )
+        .unwrap();
+
+        let config = Config::from_args(args(&[
+            "--config",
+            path.to_str().unwrap(),
+            "--port=9090",
+            "--keep-alive-timeout",
+            "30",
+            "--compression-min-size=0",
+            "--retry-after=3",
+            "--pool-log-level=WARN",
+            "--cache-control=/static/*path=max-age=60",
+        ]));
+
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            config.unwrap(),
+            Config {
+                address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+                port: 9090,
+                workers: NonZeroUsize::new(8).unwrap(),
+                keep_alive_timeout: Duration::from_secs(30),
+                max_connections: NonZeroUsize::new(100),
+                retry_after: Duration::from_secs(3),
+                pool_log_level: Some(LogLevel::Warn),
+                compression: Compression {
+                    min_size: 0,
+                    types: vec!["text/html".to_string(), "application/json".to_string()],
+                },
+                cache_control: BTreeMap::from([
+                    ("/".to_string(), "no-cache".to_string()),
+                    ("/static/*path".to_string(), "max-age=60".to_string()),
+                ]),
+                ..Config::default()
+            },
+            "the options were not read in order"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values_with_clear_errors() {
+        let cases = [
+            (
+                &["--workers", "0"][..],
+                "`--workers` should be a positive integer, not `0`",
+            ),
+            (
+                &["--port", "70000"],
+                "`--port` should be a port number, not `70000`",
+            ),
+            (
+                &["--pool-log-level", "loud"],
+                "`--pool-log-level` should be off, error, warn, info or debug, not `loud`",
+            ),
+            (
+                &["--address", "localhost"],
+                "`--address` should be an IP address, not `localhost`",
+            ),
+            (
+                &["--request-timeout=-5"],
+                "`--request-timeout` should be a positive number of seconds, not `-5`",
+            ),
+            (&["--threads", "4"], "`--threads` is not an option"),
+            (&["--workers"], "`--workers` needs a value"),
+            (&["4"], "unexpected argument `4`"),
+        ];
+
+        for (flags, error) in cases {
+            assert_eq!(
+                Config::from_args(args(flags)).unwrap_err(),
+                error,
+                "{flags:?} was not rejected correctly"
+            );
+        }
+    }
+
+    #[test]
+    fn tells_where_the_config_file_is_invalid() {
+        let path = env::temp_dir().join(format!("web-server-{}-invalid.toml", process::id()));
+        fs::write(&path, "workers = 2\nport =\n").unwrap();
+
+        let error = Config::from_args(args(&["--config", path.to_str().unwrap()]));
+
+        fs::remove_file(&path).unwrap();
+
+        let error = error.unwrap_err();
+        let location = format!("{}, line 2: ", path.display());
+
+        assert!(
+            error.starts_with(&location) && !error.contains('\n'),
+            "the error does not tell where the file is invalid on a single line: {error:?}"
+        );
+    }
+
+    #[test]
+    fn lets_the_workers_and_their_queue_be_busy_by_default() {
+        let config = Config::from_args(args(&["--workers", "2"])).unwrap();
+
+        assert_eq!(
+            config.max_connections(),
+            18,
+            "the default connection limit is different"
+        );
+
+        let config = Config::from_args(args(&["--workers=2", "--queue-capacity=0"])).unwrap();
+
+        assert_eq!(
+            config.max_connections(),
+            2,
+            "the connection limit does not follow the queue capacity"
+        );
+
+        let config = Config::from_args(args(&["--workers=2", "--max-connections=1"])).unwrap();
+
+        assert_eq!(
+            config.max_connections(),
+            1,
+            "the connection limit was not set"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    cell::Cell,
+    io::{self, ErrorKind, Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+/// A [`TcpStream`] whose reads and writes share a time budget, instead
+/// of every read and write getting its own timeout. Like [`TcpStream`],
+/// it is read and written through shared references.
+#[derive(Debug)]
+pub struct Connection {
+    stream: TcpStream,
+    deadline: Cell<Option<Instant>>,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> Connection {
+        Connection {
+            stream,
+            deadline: Cell::new(None),
+        }
+    }
+
+    /// Gives the reads and writes from now on `budget` to complete
+    /// in, or as much time as they need if `budget` is `None`.
+    pub fn set_budget(&self, budget: Option<Duration>) {
+        self.deadline
+            .set(budget.map(|budget| Instant::now() + budget));
+    }
+
+    /// Returns how much of the budget is left, or `None` if there is no budget.
+    pub fn remaining_budget(&self) -> Option<Duration> {
+        self.deadline
+            .get()
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    // `None` if there is no budget, or an error if the budget is spent
+    fn timeout(&self) -> io::Result<Option<Duration>> {
+        match self.remaining_budget() {
+            Some(remaining) if remaining.is_zero() => Err(ErrorKind::TimedOut.into()),
+            remaining => Ok(remaining),
+        }
+    }
+}
+
+impl Read for &Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.set_read_timeout(self.timeout()?)?;
+        (&self.stream).read(buf)
+    }
+}
+
+impl Write for &Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.set_write_timeout(self.timeout()?)?;
+        (&self.stream).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.stream).flush()
+    }
+}
+
+/// Returns `true` if `e` was caused by a spent budget. Depending on the
+/// platform, a read or write that times out fails with different kinds.
+pub fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::net::TcpListener;
+
+    #[test]
+    fn reads_fail_once_the_budget_is_spent() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let connection = Connection::new(listener.accept().unwrap().0);
+
+        (&client).write_all(b"a").unwrap();
+        connection.set_budget(Some(Duration::from_millis(50)));
+
+        let mut buf = [0; 2];
+        assert_eq!(
+            (&connection).read(&mut buf).unwrap(),
+            1,
+            "the sent byte was not read"
+        );
+
+        let started_at = Instant::now();
+        let e = (&connection).read(&mut buf).unwrap_err();
+
+        assert!(is_timeout(&e), "the read failed with {e}");
+        assert!(
+            started_at.elapsed() < Duration::from_secs(5),
+            "the read waited longer than the budget"
+        );
+        assert!(
+            is_timeout(&(&connection).read(&mut buf).unwrap_err()),
+            "a read was attempted after the budget was spent"
+        );
+    }
+}
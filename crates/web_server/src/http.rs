@@ -0,0 +1,15 @@
+//! The parts of HTTP/1.1 the web server needs.
+
+mod date;
+mod headers;
+mod mime;
+mod percent_encoding;
+mod request;
+mod response;
+
+pub use date::{format_date, parse_date, DateTime};
+pub use headers::Headers;
+pub use mime::content_type;
+pub use percent_encoding::percent_decode;
+pub use request::{Limits, Request, RequestError};
+pub use response::{Body, Response, Status};
@@ -0,0 +1,194 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A point in time broken down in UTC, to the second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u64,
+
+    /// From 1 to 12.
+    pub month: u64,
+
+    pub day: u64,
+    pub hour: u64,
+    pub minute: u64,
+    pub second: u64,
+
+    /// From 0 for Sunday to 6 for Saturday.
+    pub weekday: u64,
+}
+
+impl DateTime {
+    /// Breaks `time` down. Times before 1970 are clamped to 1970.
+    pub fn new(time: SystemTime) -> DateTime {
+        let seconds = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let days = seconds / 86_400;
+        let seconds_of_day = seconds % 86_400;
+        let (year, month, day) = civil_from_days(days);
+
+        DateTime {
+            year,
+            month,
+            day,
+            hour: seconds_of_day / 3600,
+            minute: seconds_of_day / 60 % 60,
+            second: seconds_of_day % 60,
+
+            // 1970-01-01 was a Thursday
+            weekday: (days + 4) % 7,
+        }
+    }
+
+    pub fn month_name(&self) -> &'static str {
+        MONTHS[usize::try_from(self.month - 1).unwrap()]
+    }
+
+    pub fn weekday_name(&self) -> &'static str {
+        WEEKDAYS[usize::try_from(self.weekday).unwrap()]
+    }
+}
+
+/// Formats `time` like `Sun, 06 Nov 1994 08:49:37 GMT`, the
+/// format of the dates in the header fields.
+pub fn format_date(time: SystemTime) -> String {
+    let date = DateTime::new(time);
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        date.weekday_name(),
+        date.day,
+        date.month_name(),
+        date.year,
+        date.hour,
+        date.minute,
+        date.second
+    )
+}
+
+/// Parses a date formatted like [`format_date`] does, which is the only
+/// format senders are allowed to generate. Returns `None` for the obsolete
+/// formats and invalid dates, which header fields ignore anyway.
+pub fn parse_date(date: &str) -> Option<SystemTime> {
+    let mut parts = date.split(' ');
+
+    let weekday = parts.next()?.strip_suffix(',')?;
+    let day = parse_number(parts.next()?, 2)?;
+    let month = parts.next()?;
+    let year = parse_number(parts.next()?, 4)?;
+
+    let mut time = parts.next()?.split(':');
+    let hour = parse_number(time.next()?, 2)?;
+    let minute = parse_number(time.next()?, 2)?;
+    let second = parse_number(time.next()?, 2)?;
+
+    if parts.next()? != "GMT" || parts.next().is_some() || time.next().is_some() {
+        return None;
+    }
+
+    let month = MONTHS.iter().position(|name| *name == month)? as u64 + 1;
+
+    if !WEEKDAYS.contains(&weekday)
+        || !(1..=31).contains(&day)
+        || year < 1970
+        || hour > 23
+        || minute > 59
+        || second > 59
+    {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86_400 + hour * 3600 + minute * 60 + second;
+
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+fn parse_number(number: &str, digits: usize) -> Option<u64> {
+    if number.len() == digits && number.bytes().all(|byte| byte.is_ascii_digit()) {
+        number.parse().ok()
+    } else {
+        None
+    }
+}
+
+// the year, month and day of a number of days since 1970-01-01, see
+// https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+
+    // the months start in March, so that the leap day is the last day
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+
+    (year_of_era + era * 400 + u64::from(month <= 2), month, day)
+}
+
+// the other way around, for years from 1970
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let shifted_month = (month + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_and_parses_dates() {
+        let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+
+        assert_eq!(
+            format_date(time),
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "the date was not formatted correctly"
+        );
+        assert_eq!(
+            parse_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(time),
+            "the date was not parsed correctly"
+        );
+
+        let leap_day = UNIX_EPOCH + Duration::from_secs(951_782_400);
+
+        assert_eq!(
+            parse_date(&format_date(leap_day)),
+            Some(leap_day),
+            "the leap day did not survive a round trip"
+        );
+
+        for date in [
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 6 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+        ] {
+            assert_eq!(parse_date(date), None, "{date:?} was parsed");
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::path::Path;
+
+/// Returns the Content-Type of a file with the extension of `path`.
+/// Unknown extensions get the type of arbitrary binary data.
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "md" => "text/markdown; charset=utf-8",
+        "xml" => "application/xml",
+        "json" => "application/json",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guesses_the_type_from_the_extension() {
+        assert_eq!(
+            content_type(Path::new("res/Hello.HTML")),
+            "text/html; charset=utf-8",
+            "the extension was not matched case-insensitively"
+        );
+        assert_eq!(
+            content_type(Path::new("res/archive.tar.gz")),
+            "application/gzip",
+            "the last extension was not used"
+        );
+        assert_eq!(
+            content_type(Path::new("res/LICENSE")),
+            "application/octet-stream",
+            "a file without an extension got a specific type"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+/// Decodes the `%XX` escapes of a path. Returns `None` if an
+/// escape is not valid or if the result is not valid UTF-8.
+pub fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = after.get(..2)?;
+            let hex = std::str::from_utf8(hex).ok()?;
+
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_escapes_and_rejects_invalid_ones() {
+        assert_eq!(
+            percent_decode("caf%C3%A9%20menu").as_deref(),
+            Some("café menu"),
+            "the escapes were not decoded"
+        );
+        assert_eq!(percent_decode("100%"), None, "a cut escape was decoded");
+        assert_eq!(percent_decode("%+1"), None, "a non-hex escape was decoded");
+        assert_eq!(percent_decode("%FF"), None, "invalid UTF-8 was decoded");
+    }
+}
@@ -0,0 +1,774 @@
+use super::{Headers, Status};
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    io::{self, BufRead, Read},
+};
+
+// long enough for a chunk size with a few extensions
+const MAX_CHUNK_LINE_LENGTH: usize = 1024;
+
+/// The HTTP versions the web server understands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    Http10,
+    Http11,
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Version::Http10 => f.write_str("HTTP/1.0"),
+            Version::Http11 => f.write_str("HTTP/1.1"),
+        }
+    }
+}
+
+/// A request read by [`Request::read_head`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+
+    /// The path of the request target, still percent-encoded.
+    pub path: String,
+
+    /// The part of the request target after the `?`, if any.
+    pub query: Option<String>,
+
+    pub version: Version,
+    pub headers: Headers,
+
+    /// The body, with the chunked transfer coding already removed.
+    pub body: Vec<u8>,
+
+    /// The decoded path parameters, filled in by the
+    /// [`Router`](crate::router::Router) from the pattern of the route.
+    pub params: HashMap<String, String>,
+
+    // the bytes of the header lines, counted again with the trailer fields
+    headers_size: usize,
+}
+
+/// How large the parts of a request can be before it is rejected,
+/// so that a client cannot make the server read without end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The longest request line, in bytes.
+    pub request_line_length: usize,
+
+    /// The most header fields, trailer fields included.
+    pub header_count: usize,
+
+    /// The most bytes of header fields, trailer fields included.
+    pub headers_size: usize,
+
+    /// The largest body, in bytes, once the chunked transfer coding is removed.
+    pub body_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            request_line_length: 8 * 1024,
+            header_count: 100,
+            headers_size: 16 * 1024,
+            body_size: 1024 * 1024,
+        }
+    }
+}
+
+/// A part of a request that can exceed its [`Limits`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    RequestLine,
+    Headers,
+    Body,
+}
+
+/// The reason why [`Request::read_head`] or [`Request::read_body`]
+/// could not read a request.
+#[derive(Debug)]
+pub enum RequestError {
+    /// The request does not follow the HTTP/1.1 syntax.
+    Malformed(&'static str),
+
+    /// The request exceeds one of its [`Limits`].
+    TooLarge(Limit),
+
+    /// The connection failed or timed out.
+    Io(io::Error),
+}
+
+impl RequestError {
+    /// Returns the status of the response the client should
+    /// get, or `None` if no response can be sent.
+    pub fn status(&self) -> Option<Status> {
+        match self {
+            RequestError::Malformed(_) => Some(Status::BadRequest),
+            RequestError::TooLarge(Limit::RequestLine) => Some(Status::UriTooLong),
+            RequestError::TooLarge(Limit::Headers) => Some(Status::RequestHeaderFieldsTooLarge),
+            RequestError::TooLarge(Limit::Body) => Some(Status::ContentTooLarge),
+            RequestError::Io(_) => None,
+        }
+    }
+}
+
+impl Display for RequestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Malformed(reason) => write!(f, "the request is malformed: {reason}"),
+            RequestError::TooLarge(Limit::RequestLine) => {
+                f.write_str("the request line is too long")
+            }
+            RequestError::TooLarge(Limit::Headers) => {
+                f.write_str("the header fields are too large")
+            }
+            RequestError::TooLarge(Limit::Body) => f.write_str("the body is too large"),
+            RequestError::Io(e) => write!(f, "the request could not be read: {e}"),
+        }
+    }
+}
+
+impl Error for RequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RequestError::Malformed(_) | RequestError::TooLarge(_) => None,
+            RequestError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for RequestError {
+    fn from(e: io::Error) -> RequestError {
+        RequestError::Io(e)
+    }
+}
+
+impl Request {
+    /// Reads the request line and the header fields of the next request
+    /// from `reader`, leaving the body to [`Request::read_body`]. Returns
+    /// `Ok(None)` if the connection was closed before a new request started.
+    pub fn read_head(
+        reader: &mut impl BufRead,
+        limits: &Limits,
+    ) -> Result<Option<Request>, RequestError> {
+        let request_line = loop {
+            match read_line(
+                reader,
+                limits.request_line_length,
+                RequestError::TooLarge(Limit::RequestLine),
+            )? {
+                None => return Ok(None),
+                Some(line) if !line.is_empty() => break line,
+                // empty lines before a request are allowed and ignored
+                Some(_) => {}
+            }
+        };
+
+        let (method, target, version) = parse_request_line(&request_line)?;
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+
+        let mut headers_size = 0;
+        let headers = read_headers(reader, limits, &mut 0, &mut headers_size)?;
+
+        if version == Version::Http11 && headers.get_all("Host").count() != 1 {
+            return Err(RequestError::Malformed(
+                "an HTTP/1.1 request needs exactly one Host field",
+            ));
+        }
+
+        Ok(Some(Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query,
+            version,
+            headers,
+            body: Vec::new(),
+            params: HashMap::new(),
+            headers_size,
+        }))
+    }
+
+    /// Reads the body of a request whose head was read by
+    /// [`Request::read_head`] from `reader`.
+    pub fn read_body(
+        &mut self,
+        reader: &mut impl BufRead,
+        limits: &Limits,
+    ) -> Result<(), RequestError> {
+        self.body = read_body(
+            reader,
+            self.version,
+            &self.headers,
+            self.headers_size,
+            limits,
+        )?;
+        Ok(())
+    }
+
+    /// Returns `true` if the client wants to keep the connection open
+    /// after the response. HTTP/1.1 connections stay open unless the client
+    /// asks for them to be closed, HTTP/1.0 connections the other way around.
+    pub fn keep_alive(&self) -> bool {
+        let has_option = |option: &str| {
+            self.headers
+                .get_all("Connection")
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim_matches([' ', '\t']).eq_ignore_ascii_case(option))
+        };
+
+        match self.version {
+            Version::Http11 => !has_option("close"),
+            Version::Http10 => has_option("keep-alive"),
+        }
+    }
+
+    /// Returns the path parameter called `name`, if any.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+fn parse_request_line(line: &str) -> Result<(&str, &str, Version), RequestError> {
+    let mut parts = line.split(' ');
+
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(RequestError::Malformed(
+            "the request line does not have three parts",
+        ));
+    };
+
+    if !is_token(method) {
+        return Err(RequestError::Malformed("the method is not a token"));
+    }
+
+    let is_valid_target =
+        target == "*" || (target.starts_with('/') && target.bytes().all(|b| b.is_ascii_graphic()));
+
+    if !is_valid_target {
+        return Err(RequestError::Malformed("the request target is not valid"));
+    }
+
+    let version = match version {
+        "HTTP/1.1" => Version::Http11,
+        "HTTP/1.0" => Version::Http10,
+        _ => return Err(RequestError::Malformed("the HTTP version is not supported")),
+    };
+
+    Ok((method, target, version))
+}
+
+// the count and size of the fields read so far are shared with the trailer fields
+fn read_headers(
+    reader: &mut impl BufRead,
+    limits: &Limits,
+    count: &mut usize,
+    size: &mut usize,
+) -> Result<Headers, RequestError> {
+    let mut headers = Headers::default();
+
+    loop {
+        let line = read_line(
+            reader,
+            limits.headers_size.saturating_sub(*size),
+            RequestError::TooLarge(Limit::Headers),
+        )?
+        .ok_or(RequestError::Malformed(
+            "the connection was closed in the middle of the header",
+        ))?;
+
+        *size += line.len();
+
+        if line.is_empty() {
+            return Ok(headers);
+        }
+
+        if line.starts_with([' ', '\t']) {
+            return Err(RequestError::Malformed(
+                "header fields folded over several lines are not supported",
+            ));
+        }
+
+        let (name, value) = line.split_once(':').ok_or(RequestError::Malformed(
+            "a header field does not have a ':'",
+        ))?;
+
+        if !is_token(name) {
+            return Err(RequestError::Malformed(
+                "the name of a header field is not a token",
+            ));
+        }
+
+        let value = value.trim_matches([' ', '\t']);
+
+        if value.bytes().any(|b| b.is_ascii_control() && b != b'\t') {
+            return Err(RequestError::Malformed(
+                "the value of a header field has control characters",
+            ));
+        }
+
+        *count += 1;
+
+        if *count > limits.header_count {
+            return Err(RequestError::TooLarge(Limit::Headers));
+        }
+
+        headers.add(name, value);
+    }
+}
+
+fn read_body(
+    reader: &mut impl BufRead,
+    version: Version,
+    headers: &Headers,
+    headers_size: usize,
+    limits: &Limits,
+) -> Result<Vec<u8>, RequestError> {
+    let mut transfer_codings = headers
+        .get_all("Transfer-Encoding")
+        .flat_map(|value| value.split(','))
+        .map(|coding| coding.trim_matches([' ', '\t']))
+        .peekable();
+
+    if transfer_codings.peek().is_some() {
+        if version == Version::Http10 {
+            return Err(RequestError::Malformed(
+                "HTTP/1.0 requests cannot have a Transfer-Encoding",
+            ));
+        }
+
+        if headers.contains("Content-Length") {
+            return Err(RequestError::Malformed(
+                "a request cannot have both a Transfer-Encoding and a Content-Length",
+            ));
+        }
+
+        let is_chunked = transfer_codings.next().is_some_and(|coding| {
+            coding.eq_ignore_ascii_case("chunked") && transfer_codings.next().is_none()
+        });
+
+        if !is_chunked {
+            return Err(RequestError::Malformed(
+                "only the chunked transfer coding is supported",
+            ));
+        }
+
+        return read_chunked_body(reader, headers, headers_size, limits);
+    }
+
+    match content_length(headers)? {
+        Some(length) if length > limits.body_size => Err(RequestError::TooLarge(Limit::Body)),
+        Some(length) => read_exactly(reader, length),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn content_length(headers: &Headers) -> Result<Option<u64>, RequestError> {
+    let mut length = None;
+
+    // repeated lengths are allowed as long as they are all the same
+    for value in headers
+        .get_all("Content-Length")
+        .flat_map(|value| value.split(','))
+    {
+        let value = value.trim_matches([' ', '\t']);
+
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(RequestError::Malformed(
+                "the Content-Length is not a number",
+            ));
+        }
+
+        let value = value
+            .parse()
+            .map_err(|_| RequestError::Malformed("the Content-Length is too big"))?;
+
+        if length.is_some_and(|length| length != value) {
+            return Err(RequestError::Malformed(
+                "the request has different Content-Length values",
+            ));
+        }
+
+        length = Some(value);
+    }
+
+    Ok(length)
+}
+
+fn read_chunked_body(
+    reader: &mut impl BufRead,
+    headers: &Headers,
+    headers_size: usize,
+    limits: &Limits,
+) -> Result<Vec<u8>, RequestError> {
+    let mut body = Vec::new();
+
+    loop {
+        // the body limit is about the data, so a long line is malformed instead
+        let line = read_line(
+            reader,
+            MAX_CHUNK_LINE_LENGTH,
+            RequestError::Malformed("a chunk size line is too long"),
+        )?
+        .ok_or(RequestError::Malformed(
+            "the connection was closed in the middle of the body",
+        ))?;
+
+        // chunk extensions are ignored
+        let size = line
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches([' ', '\t']);
+
+        if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(RequestError::Malformed("the chunk size is not a number"));
+        }
+
+        let size = u64::from_str_radix(size, 16)
+            .map_err(|_| RequestError::Malformed("the chunk size is too big"))?;
+
+        if size == 0 {
+            break;
+        }
+
+        if size > limits.body_size - body.len() as u64 {
+            return Err(RequestError::TooLarge(Limit::Body));
+        }
+
+        body.append(&mut read_exactly(reader, size)?);
+
+        let not_followed = || RequestError::Malformed("a chunk is not followed by an empty line");
+
+        if read_line(reader, MAX_CHUNK_LINE_LENGTH, not_followed())?.as_deref() != Some("") {
+            return Err(not_followed());
+        }
+    }
+
+    // the trailer fields are not needed by the server
+    let mut count = headers.iter().count();
+    let mut size = headers_size;
+
+    read_headers(reader, limits, &mut count, &mut size)?;
+    Ok(body)
+}
+
+fn read_exactly(reader: &mut impl BufRead, length: u64) -> Result<Vec<u8>, RequestError> {
+    let mut bytes = Vec::new();
+
+    // reading through `take` does not trust the length with the allocation
+    let read_length = reader.take(length).read_to_end(&mut bytes)?;
+
+    if u64::try_from(read_length).ok() == Some(length) {
+        Ok(bytes)
+    } else {
+        Err(RequestError::Malformed(
+            "the connection was closed in the middle of the body",
+        ))
+    }
+}
+
+// lines end with CRLF, but a lone LF is accepted too, and the lines
+// longer than `max_length` without them are rejected with `too_long`
+fn read_line(
+    reader: &mut impl BufRead,
+    max_length: usize,
+    too_long: RequestError,
+) -> Result<Option<String>, RequestError> {
+    let mut line = Vec::new();
+
+    // reading through `take` stops at the limit even if the line goes on
+    let max_read = u64::try_from(max_length)
+        .unwrap_or(u64::MAX)
+        .saturating_add(2);
+
+    if reader.take(max_read).read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+
+    if line.last() == Some(&b'\n') {
+        line.pop();
+
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+    } else if line.len() as u64 == max_read {
+        return Err(too_long);
+    } else {
+        return Err(RequestError::Malformed(
+            "the connection was closed in the middle of a line",
+        ));
+    }
+
+    if line.len() > max_length {
+        return Err(too_long);
+    }
+
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| RequestError::Malformed("a line is not valid UTF-8"))
+}
+
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // reads a whole request, like the server does
+    fn read_request(
+        reader: &mut impl BufRead,
+        limits: &Limits,
+    ) -> Result<Option<Request>, RequestError> {
+        let Some(mut request) = Request::read_head(reader, limits)? else {
+            return Ok(None);
+        };
+
+        request.read_body(reader, limits)?;
+        Ok(Some(request))
+    }
+
+    fn read(input: &str) -> Result<Option<Request>, RequestError> {
+        read_request(&mut input.as_bytes(), &Limits::default())
+    }
+
+    #[test]
+    fn reads_the_parts_of_a_request() {
+        let request = read(
+            "GET /search?q=rust HTTP/1.1\r\n\
+             Host: localhost:7878\r\n\
+             Accept:  text/html \r\n\
+             \r\n",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(request.method, "GET", "the method is different");
+        assert_eq!(request.path, "/search", "the path is different");
+        assert_eq!(
+            request.query.as_deref(),
+            Some("q=rust"),
+            "the query is different"
+        );
+        assert_eq!(request.version, Version::Http11, "the version is different");
+        assert_eq!(
+            request.headers.get("accept"),
+            Some("text/html"),
+            "the header fields were not read"
+        );
+        assert!(request.body.is_empty(), "the request has a body");
+    }
+
+    #[test]
+    fn reads_bodies_framed_by_content_length_or_chunks() {
+        let mut input = "POST /a HTTP/1.1\r\n\
+                         Host: localhost\r\n\
+                         Content-Length: 5\r\n\
+                         \r\n\
+                         hello\
+                         POST /b HTTP/1.1\r\n\
+                         Host: localhost\r\n\
+                         Transfer-Encoding: chunked\r\n\
+                         \r\n\
+                         5;name=value\r\n\
+                         hello\r\n\
+                         7\r\n\
+                         , world\r\n\
+                         0\r\n\
+                         Expires: never\r\n\
+                         \r\n"
+            .as_bytes();
+
+        let first = read_request(&mut input, &Limits::default())
+            .unwrap()
+            .unwrap();
+        let second = read_request(&mut input, &Limits::default())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(first.body, b"hello", "the Content-Length body is different");
+        assert_eq!(
+            second.body, b"hello, world",
+            "the chunked body is different"
+        );
+        assert!(
+            read_request(&mut input, &Limits::default())
+                .unwrap()
+                .is_none(),
+            "a request was read after the last one"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let malformed_requests = [
+            "GET /\r\n\r\n",
+            "GET / HTTP/1.1 extra\r\nHost: a\r\n\r\n",
+            "G(T / HTTP/1.1\r\nHost: a\r\n\r\n",
+            "GET index.html HTTP/1.1\r\nHost: a\r\n\r\n",
+            "GET / HTTP/2.0\r\nHost: a\r\n\r\n",
+            "GET / HTTP/1.1\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: a\r\nNo colon\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: a\r\nName : value\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: a\r\nName: value\r\n folded\r\n\r\n",
+            "GET / HTTP/1.1\r\nHost: a\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: -1\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 1, 2\r\n\r\nab",
+            "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nab",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: gzip\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\
+             Content-Length: 2\r\n\r\n2\r\nab\r\n0\r\n\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\nx\r\n",
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nabc\r\n",
+            "POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        ];
+
+        for request in malformed_requests {
+            assert!(
+                matches!(read(request), Err(RequestError::Malformed(_))),
+                "the malformed request {request:?} was not rejected"
+            );
+        }
+
+        let long_chunk_line = format!(
+            "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+             1;{}\r\na\r\n0\r\n\r\n",
+            "a".repeat(MAX_CHUNK_LINE_LENGTH)
+        );
+
+        assert!(
+            matches!(read(&long_chunk_line), Err(RequestError::Malformed(_))),
+            "a chunk size line that is too long was not rejected as malformed"
+        );
+    }
+
+    #[test]
+    fn keeps_alive_by_version_and_connection_options() {
+        let cases = [
+            ("HTTP/1.1", "", true),
+            ("HTTP/1.1", "Connection: upgrade, Close\r\n", false),
+            ("HTTP/1.0", "", false),
+            ("HTTP/1.0", "Connection: keep-alive\r\n", true),
+        ];
+
+        for (version, connection, keep_alive) in cases {
+            let request = read(&format!(
+                "GET / {version}\r\nHost: localhost\r\n{connection}\r\n"
+            ))
+            .unwrap()
+            .unwrap();
+
+            assert_eq!(
+                request.keep_alive(),
+                keep_alive,
+                "{version} with {connection:?} was not handled"
+            );
+        }
+    }
+
+    #[test]
+    fn returns_none_when_the_connection_is_closed_between_requests() {
+        assert!(
+            read("").unwrap().is_none(),
+            "a request was read from an empty connection"
+        );
+        assert!(
+            read("\r\n").unwrap().is_none(),
+            "a request was read from an empty line"
+        );
+    }
+
+    #[test]
+    fn rejects_requests_exceeding_the_limits() {
+        let limits = Limits {
+            request_line_length: 32,
+            header_count: 2,
+            headers_size: 64,
+            body_size: 8,
+        };
+
+        let long_value = "a".repeat(64);
+
+        let cases = [
+            (
+                format!("GET /{long_value} HTTP/1.1\r\n"),
+                Limit::RequestLine,
+            ),
+            (format!("GET /{long_value}"), Limit::RequestLine),
+            (
+                "GET / HTTP/1.1\r\nHost: a\r\nA: 1\r\nB: 2\r\n\r\n".to_string(),
+                Limit::Headers,
+            ),
+            (
+                format!("GET / HTTP/1.1\r\nHost: a\r\nA: {long_value}\r\n\r\n"),
+                Limit::Headers,
+            ),
+            (
+                "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 9\r\n\r\n".to_string(),
+                Limit::Body,
+            ),
+            (
+                "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 5\r\nhello\r\n5\r\nworld\r\n0\r\n\r\n"
+                    .to_string(),
+                Limit::Body,
+            ),
+            (
+                "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 0\r\nA: 1\r\n\r\n"
+                    .to_string(),
+                Limit::Headers,
+            ),
+        ];
+
+        for (request, limit) in cases {
+            assert!(
+                matches!(
+                    read_request(&mut request.as_bytes(), &limits),
+                    Err(RequestError::TooLarge(exceeded)) if exceeded == limit
+                ),
+                "{request:?} did not exceed the {limit:?} limit"
+            );
+        }
+
+        // the trailer field fits in what the parsed header fields leave,
+        // but not in what their lines, with the padding, leave
+        let request = format!(
+            "POST / HTTP/1.1\r\nHost:{}a\r\nTransfer-Encoding: chunked\r\n\r\n\
+             0\r\nB: 1234567890\r\n\r\n",
+            " ".repeat(21)
+        );
+        let trailer_limits = Limits {
+            header_count: 3,
+            ..limits
+        };
+
+        assert!(
+            matches!(
+                read_request(&mut request.as_bytes(), &trailer_limits),
+                Err(RequestError::TooLarge(Limit::Headers))
+            ),
+            "the trailer fields were not counted like the header fields"
+        );
+
+        let request = "POST /12345678 HTTP/1.1\r\nHost: a\r\nContent-Length: 8\r\n\r\nabcdefgh";
+
+        assert!(
+            read_request(&mut request.as_bytes(), &limits).is_ok(),
+            "a request within the limits was rejected"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use super::Headers;
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    sync::Arc,
+};
+
+/// The status of a [`Response`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    PartialContent,
+    NotModified,
+    BadRequest,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    RequestTimeout,
+    ContentTooLarge,
+    UriTooLong,
+    RangeNotSatisfiable,
+    RequestHeaderFieldsTooLarge,
+    InternalServerError,
+    ServiceUnavailable,
+}
+
+impl Status {
+    pub fn code(self) -> u16 {
+        match self {
+            Status::Ok => 200,
+            Status::PartialContent => 206,
+            Status::NotModified => 304,
+            Status::BadRequest => 400,
+            Status::Forbidden => 403,
+            Status::NotFound => 404,
+            Status::MethodNotAllowed => 405,
+            Status::RequestTimeout => 408,
+            Status::ContentTooLarge => 413,
+            Status::UriTooLong => 414,
+            Status::RangeNotSatisfiable => 416,
+            Status::RequestHeaderFieldsTooLarge => 431,
+            Status::InternalServerError => 500,
+            Status::ServiceUnavailable => 503,
+        }
+    }
+
+    pub fn reason(self) -> &'static str {
+        match self {
+            Status::Ok => "OK",
+            Status::PartialContent => "PARTIAL CONTENT",
+            Status::NotModified => "NOT MODIFIED",
+            Status::BadRequest => "BAD REQUEST",
+            Status::Forbidden => "FORBIDDEN",
+            Status::NotFound => "NOT FOUND",
+            Status::MethodNotAllowed => "METHOD NOT ALLOWED",
+            Status::RequestTimeout => "REQUEST TIMEOUT",
+            Status::ContentTooLarge => "CONTENT TOO LARGE",
+            Status::UriTooLong => "URI TOO LONG",
+            Status::RangeNotSatisfiable => "RANGE NOT SATISFIABLE",
+            Status::RequestHeaderFieldsTooLarge => "REQUEST HEADER FIELDS TOO LARGE",
+            Status::InternalServerError => "INTERNAL SERVER ERROR",
+            Status::ServiceUnavailable => "SERVICE UNAVAILABLE",
+        }
+    }
+}
+
+/// The body of a [`Response`].
+#[derive(Debug)]
+pub enum Body {
+    Bytes(Vec<u8>),
+
+    /// Bytes kept elsewhere too, like in a cache, so that they
+    /// are not copied for every response sending them.
+    Shared(Arc<[u8]>),
+
+    /// The `length` bytes of a file from its current position,
+    /// which are streamed instead of being read into memory.
+    File {
+        file: File,
+        length: u64,
+    },
+
+    /// Ranges of a file, each after some bytes of its own, then `tail`.
+    /// Makes up a multipart body without reading the file into memory.
+    FileRanges {
+        file: File,
+        ranges: Vec<(Vec<u8>, Range<u64>)>,
+        tail: Vec<u8>,
+    },
+}
+
+impl Body {
+    pub fn length(&self) -> u64 {
+        match self {
+            Body::Bytes(bytes) => bytes.len() as u64,
+            Body::Shared(bytes) => bytes.len() as u64,
+            Body::File { length, .. } => *length,
+            Body::FileRanges { ranges, tail, .. } => {
+                let ranges: u64 = ranges
+                    .iter()
+                    .map(|(head, range)| head.len() as u64 + (range.end - range.start))
+                    .sum();
+
+                ranges + tail.len() as u64
+            }
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Body {
+        Body::Bytes(bytes)
+    }
+}
+
+impl From<String> for Body {
+    fn from(s: String) -> Body {
+        Body::Bytes(s.into_bytes())
+    }
+}
+
+impl From<&str> for Body {
+    fn from(s: &str) -> Body {
+        Body::Bytes(s.as_bytes().to_vec())
+    }
+}
+
+/// A response to a [`Request`](super::Request).
+#[derive(Debug)]
+pub struct Response {
+    pub status: Status,
+    pub headers: Headers,
+    pub body: Body,
+}
+
+impl Response {
+    pub fn new(status: Status, body: impl Into<Body>) -> Response {
+        Response {
+            status,
+            headers: Headers::default(),
+            body: body.into(),
+        }
+    }
+
+    /// Writes the response, adding the Content-Length of the body unless
+    /// the status is [`Status::NotModified`], which has no Content-Type either.
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        // written at once so that the head is not sent in many small packets
+        let mut head = Vec::new();
+
+        write!(
+            head,
+            "HTTP/1.1 {} {}\r\n",
+            self.status.code(),
+            self.status.reason()
+        )?;
+
+        for (name, value) in &self.headers {
+            // kept on a 304 only to tell which fields the 200 would have, like Vary
+            if self.status == Status::NotModified && name.eq_ignore_ascii_case("Content-Type") {
+                continue;
+            }
+
+            write!(head, "{name}: {value}\r\n")?;
+        }
+
+        // a 304 has no body, and its length would be mistaken for the one of the file
+        if self.status != Status::NotModified {
+            write!(head, "Content-Length: {}\r\n", self.body.length())?;
+        }
+
+        write!(head, "\r\n")?;
+
+        writer.write_all(&head)?;
+
+        match &self.body {
+            Body::Bytes(bytes) => writer.write_all(bytes)?,
+            Body::Shared(bytes) => writer.write_all(bytes)?,
+            Body::File { file, length } => copy_file(file, *length, writer)?,
+            Body::FileRanges { file, ranges, tail } => {
+                for (head, range) in ranges {
+                    writer.write_all(head)?;
+                    (&*file).seek(SeekFrom::Start(range.start))?;
+                    copy_file(file, range.end - range.start, writer)?;
+                }
+
+                writer.write_all(tail)?;
+            }
+        }
+
+        writer.flush()
+    }
+}
+
+fn copy_file(file: &File, length: u64, writer: &mut impl Write) -> io::Result<()> {
+    let copied = io::copy(&mut file.take(length), writer)?;
+
+    // the length was already sent, so a file that
+    // shrank in the meantime cannot be answered anymore
+    if copied < length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_the_status_line_headers_and_body() {
+        let mut response = Response::new(Status::NotFound, "missing");
+        response.headers.add("Content-Type", "text/plain");
+
+        let mut output = Vec::new();
+        response.write_to(&mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 404 NOT FOUND\r\n\
+             Content-Type: text/plain\r\n\
+             Content-Length: 7\r\n\
+             \r\n\
+             missing",
+            "the response was not written correctly"
+        );
+    }
+
+    #[test]
+    fn writes_neither_the_length_nor_the_type_of_a_304() {
+        let mut response = Response::new(Status::NotModified, Vec::new());
+        response.headers.add("Content-Type", "text/html");
+        response.headers.add("Vary", "Accept-Encoding");
+
+        let mut output = Vec::new();
+        response.write_to(&mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 304 NOT MODIFIED\r\n\
+             Vary: Accept-Encoding\r\n\
+             \r\n",
+            "the 304 has fields of a body"
+        );
+    }
+}
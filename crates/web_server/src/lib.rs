@@ -1,35 +1,79 @@
 //! Contains an implementation of a [`ThreadPool`]
 //! that can be used for serving web requests.
 
+mod builder;
+mod cancellation;
+mod logger;
+mod panic_policy;
 mod panic_unwind;
+mod priority;
+mod queue_full;
+mod scheduler;
+mod scope;
+mod shared_state;
+mod shutdown;
+mod stats;
+mod supervisor;
+mod task;
+mod thread_settings;
+mod timer;
 mod types_traits;
 mod worker;
 
 use std::{
+    mem,
     num::NonZeroUsize,
-    sync::{
-        mpsc::{self, Sender},
-        Arc, Mutex,
-    },
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
 };
 
-use types_traits::Job;
+use scheduler::JobOptions;
+use shared_state::SharedState;
+use supervisor::Supervisor;
+use timer::Timer;
 use worker::Worker;
 
+pub use builder::ThreadPoolBuilder;
+pub use cancellation::CancellationToken;
+pub use logger::{LogLevel, Logger, PoolEvent, StderrLogger};
+pub use panic_policy::PanicPolicy;
+pub use priority::Priority;
+pub use queue_full::QueueFullError;
+pub use scope::Scope;
+pub use shutdown::ShutdownReport;
+pub use stats::{Histogram, PoolStats};
+pub use task::{TaskError, TaskHandle};
+pub use timer::ScheduledHandle;
+
 /// Manages a pool with a specified number of threads. See
 /// [`ThreadPool::build`] for an example.
+///
+/// Workers whose threads die are replaced with new workers, see
+/// [`ThreadPool::restarts`]. The number of threads can be changed
+/// with [`ThreadPool::set_size`], or by the pool itself when it is
+/// built with a [`ThreadPoolBuilder::max_size`].
+///
+/// Dropping the pool waits for all the queued jobs to finish. Use
+/// [`ThreadPool::shutdown`] or [`ThreadPool::shutdown_now`] to bound
+/// the time spent waiting.
 #[derive(Debug)]
 pub struct ThreadPool {
-    sender: Sender<Job>,
+    // None only after the pool was shut down
+    supervisor: Option<Supervisor>,
+
+    workers: Arc<Mutex<Vec<Worker>>>,
+    state: Arc<SharedState>,
+    panic_policy: PanicPolicy,
 
-    #[allow(dead_code)] // used for Drop side-effect and in tests
-    workers: Vec<Worker>,
+    // started by the first delayed or periodic job
+    timer: OnceLock<Timer>,
 }
 
 impl ThreadPool {
     /// Creates a new [`ThreadPool`].
     ///
     /// The `size` parameter specifies the number of threads in the pool.
+    /// Use [`ThreadPoolBuilder`] to configure the pool further.
     ///
     /// # Examples
     ///
@@ -45,28 +89,112 @@ impl ThreadPool {
     ///     println!("Thread pool thread has id {:?}.", thread::current().id());
     /// });
     /// ```
+    #[must_use]
     pub fn build(size: NonZeroUsize) -> ThreadPool {
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        ThreadPoolBuilder::new(size).build()
+    }
 
-        let mut workers = Vec::with_capacity(size.get());
+    fn from_builder(builder: &ThreadPoolBuilder) -> ThreadPool {
+        let state = Arc::new(SharedState::new(builder));
+        let workers = Arc::new(Mutex::new(Vec::with_capacity(builder.size.get())));
+
+        // created before the workers so that no dead worker goes unnoticed
+        let supervisor = Supervisor::new(
+            Arc::clone(&workers),
+            Arc::clone(&state),
+            builder.panic_policy,
+        );
+
+        let tp = ThreadPool {
+            supervisor: Some(supervisor),
+            workers,
+            state,
+            panic_policy: builder.panic_policy,
+            timer: OnceLock::new(),
+        };
 
-        for id in 0..size.get() {
-            workers.push(Worker::new(
-                id,
-                Arc::clone(&receiver),
-                panic_unwind::abort_process,
-            ));
+        tp.resize(builder.size, builder.max_size.max(builder.size));
+        tp
+    }
+
+    /// Changes the number of threads in the [`ThreadPool`] to `size`.
+    ///
+    /// New threads are spawned right away. When shrinking, idle threads
+    /// exit right away, while busy threads exit after finishing their
+    /// current job. A pool built with a [`ThreadPoolBuilder::max_size`]
+    /// stops growing and shrinking on its own and keeps `size` threads.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::num::NonZeroUsize;
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+    ///
+    /// tp.set_size(NonZeroUsize::new(8usize).unwrap());
+    /// assert_eq!(tp.size(), 8);
+    /// ```
+    pub fn set_size(&self, size: NonZeroUsize) {
+        self.resize(size, size);
+    }
+
+    /// Returns the number of threads in the [`ThreadPool`], not counting
+    /// the threads that are about to exit after a [`ThreadPool::set_size`]
+    /// or after being idle.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.state.size()
+    }
+
+    fn resize(&self, min_size: NonZeroUsize, max_size: NonZeroUsize) {
+        let mut workers = self
+            .workers
+            .lock()
+            .expect("workers lock should not be poisoned");
+
+        let size = min_size.get();
+        let previous_size = self.state.resize(size, max_size.get());
+
+        if previous_size > size {
+            self.state.scheduler().retire_workers(previous_size - size);
         }
 
-        ThreadPool { sender, workers }
+        for _ in previous_size..size {
+            self.spawn_worker(&mut workers);
+        }
+    }
+
+    // the jobs that are given to the pool while all of its
+    // workers are busy get a new worker, if the pool may grow
+    fn grow_if_busy(&self) {
+        if self.state.is_busy() && self.state.try_grow() {
+            let mut workers = self
+                .workers
+                .lock()
+                .expect("workers lock should not be poisoned");
+
+            self.spawn_worker(&mut workers);
+        }
+    }
+
+    fn spawn_worker(&self, workers: &mut Vec<Worker>) {
+        workers.push(Worker::new(
+            self.state.next_worker_id(),
+            Arc::clone(&self.state),
+            self.panic_policy,
+        ));
     }
 
     /// Runs a given piece of code on a thread from the [`ThreadPool`].
     ///
+    /// If the pool was built with a [`ThreadPoolBuilder::queue_capacity`]
+    /// and the queue is full, blocks until there is room in the queue.
+    ///
     /// # Panics
     ///
-    /// If the piece of code given to `execute` panics, the process is aborted.
+    /// If the piece of code given to `execute` panics, the [`PanicPolicy`]
+    /// of the pool decides what happens. By default, the process is aborted.
     ///
     /// # Examples
     ///
@@ -90,10 +218,440 @@ impl ThreadPool {
     where
         F: FnOnce() + Send + 'static,
     {
-        let job = Box::new(f);
+        self.execute_with_options(f, &JobOptions::default());
+    }
+
+    /// Like [`ThreadPool::execute`], but the piece of code waits in the
+    /// queue in the lane of `priority`. Queued pieces of code with a higher
+    /// priority are executed before the ones with a lower priority.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::num::NonZeroUsize;
+    /// use web_server::{Priority, ThreadPool};
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(4usize).unwrap());
+    ///
+    /// tp.execute_with_priority(|| println!("Generating a report."), Priority::Low);
+    /// tp.execute_with_priority(|| println!("Answering a health check."), Priority::High);
+    /// ```
+    pub fn execute_with_priority<F>(&self, f: F, priority: Priority)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.execute_with_options(
+            f,
+            &JobOptions {
+                priority,
+                token: None,
+            },
+        );
+    }
 
-        if let Err(e) = self.sender.send(job) {
-            panic!("send should not fail: {e}");
+    /// Like [`ThreadPool::execute_with_priority`], but the piece of code
+    /// is dropped without being executed if `token` is cancelled before
+    /// a thread picks it up.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::num::NonZeroUsize;
+    /// use web_server::{CancellationToken, Priority, ThreadPool};
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+    /// let token = CancellationToken::new();
+    ///
+    /// tp.execute_cancellable(|| println!("Hello!"), Priority::Normal, &token);
+    ///
+    /// // the client went away, so there is no one to say hello to anymore
+    /// token.cancel();
+    /// ```
+    pub fn execute_cancellable<F>(&self, f: F, priority: Priority, token: &CancellationToken)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.execute_with_options(
+            f,
+            &JobOptions {
+                priority,
+                token: Some(token.clone()),
+            },
+        );
+    }
+
+    fn execute_with_options<F>(&self, f: F, options: &JobOptions)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.grow_if_busy();
+        self.state.scheduler().push(Box::new(f), options);
+    }
+
+    /// Like [`ThreadPool::execute`], but gives the piece of code back
+    /// instead of blocking if the queue of the pool is full.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`QueueFullError`] containing `f` if the queue is full.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::num::NonZeroUsize;
+    /// use web_server::ThreadPoolBuilder;
+    ///
+    /// let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+    ///     .queue_capacity(10)
+    ///     .build();
+    ///
+    /// if let Err(e) = tp.try_execute(|| println!("Hello!")) {
+    ///     println!("Overloaded, running the job here instead.");
+    ///     e.into_job()();
+    /// }
+    /// ```
+    pub fn try_execute<F>(&self, f: F) -> Result<(), QueueFullError<F>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.grow_if_busy();
+
+        self.state
+            .scheduler()
+            .try_push(Box::new(f), &JobOptions::default())
+            .map_err(queue_full::queue_full_error)
+    }
+
+    /// Like [`ThreadPool::execute`], but gives the piece of code back if
+    /// the queue of the pool is still full after waiting for `timeout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`QueueFullError`] containing `f` if the queue is full.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{num::NonZeroUsize, time::Duration};
+    /// use web_server::ThreadPoolBuilder;
+    ///
+    /// let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+    ///     .queue_capacity(10)
+    ///     .build();
+    ///
+    /// if tp
+    ///     .execute_timeout(|| println!("Hello!"), Duration::from_millis(100))
+    ///     .is_err()
+    /// {
+    ///     println!("Overloaded, dropping the job.");
+    /// }
+    /// ```
+    pub fn execute_timeout<F>(&self, f: F, timeout: Duration) -> Result<(), QueueFullError<F>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.grow_if_busy();
+
+        self.state
+            .scheduler()
+            .push_until(
+                Box::new(f),
+                &JobOptions::default(),
+                Instant::now() + timeout,
+            )
+            .map_err(queue_full::queue_full_error)
+    }
+
+    /// Runs a given piece of code on a thread from the [`ThreadPool`]
+    /// and returns a [`TaskHandle`] that can be used to get its result.
+    ///
+    /// Unlike [`ThreadPool::execute`], a panic in the piece of code
+    /// does not abort the process. Instead, it is reported by the handle.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{num::NonZeroUsize, time::Duration};
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+    ///
+    /// let sum = tp.submit(|| (1..=10).sum::<u32>());
+    /// let panic = tp.submit(|| panic!("something went wrong"));
+    ///
+    /// assert_eq!(sum.join().unwrap(), 55);
+    ///
+    /// if panic.wait_timeout(Duration::from_secs(1)) {
+    ///     println!("{}", panic.join().unwrap_err());
+    /// }
+    /// ```
+    pub fn submit<F, R>(&self, f: F) -> TaskHandle<R>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (handle, completer) = task::new();
+        self.execute(move || completer.run(f));
+        handle
+    }
+
+    /// Runs a given piece of code on a thread from the [`ThreadPool`]
+    /// once `delay` has passed, unless the returned [`ScheduledHandle`]
+    /// is cancelled before that.
+    ///
+    /// The piece of code is queued like with [`ThreadPool::execute`] when
+    /// it is due, so it can wait in the queue a bit longer than `delay`.
+    /// Pieces of code that are not due yet when the pool shuts down are
+    /// dropped without being executed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{num::NonZeroUsize, time::Duration};
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+    ///
+    /// let handle = tp.execute_after(Duration::from_secs(30), || {
+    ///     println!("The request timed out.");
+    /// });
+    ///
+    /// // the request was answered in time
+    /// handle.cancel();
+    /// ```
+    pub fn execute_after<F>(&self, delay: Duration, f: F) -> ScheduledHandle
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.timer().execute_after(delay, Box::new(f))
+    }
+
+    /// Runs a given piece of code on a thread from the [`ThreadPool`]
+    /// every `interval`, starting one `interval` from now, until the
+    /// returned [`ScheduledHandle`] is cancelled.
+    ///
+    /// A run is queued even if the previous one is still executing, so
+    /// runs can overlap when the piece of code takes longer than `interval`.
+    /// Runs that are late because the pool is busy are not made up for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{num::NonZeroUsize, time::Duration};
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+    ///
+    /// let handle = tp.execute_every(Duration::from_secs(60), || {
+    ///     println!("Cleaning up the expired sessions.");
+    /// });
+    ///
+    /// // the server is shutting down
+    /// handle.cancel();
+    /// ```
+    pub fn execute_every<F>(&self, interval: Duration, f: F) -> ScheduledHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        assert!(!interval.is_zero(), "interval should not be zero");
+        self.timer().execute_every(interval, Arc::new(f))
+    }
+
+    fn timer(&self) -> &Timer {
+        self.timer
+            .get_or_init(|| Timer::new(Arc::clone(&self.state)))
+    }
+
+    /// Creates a [`Scope`] for running pieces of code that borrow data
+    /// from outside of the scope, which [`ThreadPool::execute`] cannot do.
+    ///
+    /// All the pieces of code given to [`Scope::execute`] are done
+    /// by the time `scope` returns. Calling `scope` from a piece of
+    /// code running on the same pool can deadlock if all the threads
+    /// of the pool end up waiting for their scopes.
+    ///
+    /// # Panics
+    ///
+    /// If `f` or any of the pieces of code given to
+    /// [`Scope::execute`] panics, `scope` panics too.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{num::NonZeroUsize, sync::Mutex};
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(4usize).unwrap());
+    ///
+    /// let lines = vec!["GET / HTTP/1.1", "Host: localhost"];
+    /// let total_length = Mutex::new(0);
+    ///
+    /// tp.scope(|s| {
+    ///     for line in &lines {
+    ///         s.execute(|| *total_length.lock().unwrap() += line.len());
+    ///     }
+    /// });
+    ///
+    /// println!("{} bytes in total.", total_length.into_inner().unwrap());
+    /// ```
+    pub fn scope<'env, F, T>(&self, f: F) -> T
+    where
+        F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+    {
+        scope::scope(self, f)
+    }
+
+    /// Stops the [`ThreadPool`] from accepting new jobs and waits
+    /// until all the queued jobs are executed or until `deadline` is reached.
+    ///
+    /// The jobs that are still in the queue when `deadline` is reached are
+    /// discarded. The jobs that are already executing cannot be interrupted,
+    /// so their threads are detached and left to finish on their own.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{
+    ///     num::NonZeroUsize,
+    ///     thread,
+    ///     time::{Duration, Instant},
+    /// };
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+    ///
+    /// for _ in 0..10 {
+    ///     tp.execute(|| thread::sleep(Duration::from_secs(1)));
+    /// }
+    ///
+    /// let report = tp.shutdown(Instant::now() + Duration::from_secs(3));
+    ///
+    /// println!(
+    ///     "{} jobs completed, {} jobs abandoned, {} jobs unfinished.",
+    ///     report.completed, report.abandoned, report.unfinished
+    /// );
+    /// ```
+    #[must_use]
+    pub fn shutdown(mut self, deadline: Instant) -> ShutdownReport {
+        self.stop(Some(deadline))
+    }
+
+    /// Stops the [`ThreadPool`] from accepting new jobs and discards all
+    /// the queued jobs without waiting for the executing ones to finish.
+    ///
+    /// This is the same as calling [`ThreadPool::shutdown`] with a
+    /// deadline that has already been reached.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::{num::NonZeroUsize, thread, time::Duration};
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+    ///
+    /// for _ in 0..10 {
+    ///     tp.execute(|| thread::sleep(Duration::from_secs(1)));
+    /// }
+    ///
+    /// let report = tp.shutdown_now();
+    /// assert!(report.abandoned > 0);
+    /// ```
+    #[must_use]
+    pub fn shutdown_now(self) -> ShutdownReport {
+        self.shutdown(Instant::now())
+    }
+
+    /// Returns the number of workers that were replaced because
+    /// their threads died, for example with [`PanicPolicy::RespawnWorker`].
+    ///
+    /// Replacement workers get new ids, so the pool keeps
+    /// its configured number of threads.
+    #[must_use]
+    pub fn restarts(&self) -> usize {
+        self.state.restarted_workers()
+    }
+
+    /// Returns a snapshot of what the [`ThreadPool`] is doing.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::num::NonZeroUsize;
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(4usize).unwrap());
+    /// let stats = tp.stats();
+    ///
+    /// println!(
+    ///     "{} jobs queued, {} of {} workers busy.",
+    ///     stats.queued_jobs,
+    ///     stats.busy_workers,
+    ///     stats.busy_workers + stats.idle_workers
+    /// );
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> PoolStats {
+        self.state.stats()
+    }
+
+    // waits for all the queued jobs to finish when there is no deadline
+    fn stop(&mut self, deadline: Option<Instant>) -> ShutdownReport {
+        // stopped first so that it does not queue jobs after the close
+        if let Some(mut timer) = self.timer.take() {
+            timer.stop();
+        }
+
+        self.state.scheduler().close();
+
+        let all_workers_exited = self.state.wait_for_workers(deadline);
+
+        if !all_workers_exited {
+            self.state.scheduler().discard_queued_jobs();
+        }
+
+        self.supervisor
+            .take()
+            .expect("supervisor should not be None")
+            .stop();
+
+        let report = ShutdownReport {
+            completed: self.state.completed_jobs(),
+            abandoned: self.state.scheduler().abandoned_jobs(),
+            unfinished: self.state.running_jobs(),
+        };
+
+        let workers = mem::take(
+            &mut *self
+                .workers
+                .lock()
+                .expect("workers lock should not be poisoned"),
+        );
+
+        for worker in workers {
+            if all_workers_exited || worker.is_finished() {
+                drop(worker);
+            } else {
+                self.state.log(&PoolEvent::WorkerDetached {
+                    worker_id: worker.id(),
+                });
+
+                worker.detach();
+            }
+        }
+
+        report
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        if self.supervisor.is_some() {
+            self.stop(None);
         }
     }
 }
@@ -103,22 +661,45 @@ mod tests {
     use super::*;
 
     use std::{
-        sync::{Barrier, OnceLock},
+        hint,
+        panic::{self, AssertUnwindSafe},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc::{self, Sender},
+            Barrier, OnceLock,
+        },
         thread::{self, ThreadId},
+        time::Duration,
     };
 
+    // blocks a worker of a pool until the returned sender is dropped
+    fn block_worker(tp: &ThreadPool) -> Sender<()> {
+        let (started_sender, started_receiver) = mpsc::channel();
+        let (release_sender, release_receiver) = mpsc::channel::<()>();
+
+        tp.execute(move || {
+            started_sender.send(()).unwrap();
+            let _ = release_receiver.recv();
+        });
+
+        started_receiver.recv().unwrap();
+        release_sender
+    }
+
     #[test]
     fn build_constructs_a_proper_thread_pool() {
         let num_threads = NonZeroUsize::new(10usize).unwrap();
         let tp = ThreadPool::build(num_threads);
 
+        let workers = tp.workers.lock().unwrap();
+
         assert_eq!(
-            tp.workers.len(),
+            workers.len(),
             num_threads.get(),
             "not enough workers were created"
         );
 
-        for (index, worker) in tp.workers.iter().enumerate() {
+        for (index, worker) in workers.iter().enumerate() {
             assert_eq!(
                 index,
                 worker.id(),
@@ -187,4 +768,545 @@ mod tests {
             "the thread id of the first worker thread was the same as the one for the second worker thread"
         );
     }
+
+    #[test]
+    fn shutdown_waits_for_queued_jobs_to_complete() {
+        let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+
+        for _ in 0..6 {
+            tp.execute(|| thread::sleep(Duration::from_millis(10)));
+        }
+
+        let report = tp.shutdown(Instant::now() + Duration::from_secs(60));
+
+        assert_eq!(
+            report,
+            ShutdownReport {
+                completed: 6,
+                abandoned: 0,
+                unfinished: 0
+            },
+            "not all jobs were completed"
+        );
+    }
+
+    #[test]
+    fn shutdown_abandons_queued_jobs_when_deadline_is_reached() {
+        let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+        let release_worker = block_worker(&tp);
+
+        for _ in 0..3 {
+            tp.execute(|| {});
+        }
+
+        let report = tp.shutdown(Instant::now() + Duration::from_millis(50));
+        drop(release_worker);
+
+        assert_eq!(
+            report,
+            ShutdownReport {
+                completed: 0,
+                abandoned: 3,
+                unfinished: 1
+            },
+            "the queued jobs were not abandoned"
+        );
+    }
+
+    #[test]
+    fn shutdown_now_does_not_execute_queued_jobs() {
+        let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+        let release_worker = block_worker(&tp);
+
+        let was_executed = Arc::new(OnceLock::new());
+
+        for _ in 0..3 {
+            let was_executed_clone = Arc::clone(&was_executed);
+
+            tp.execute(move || {
+                was_executed_clone.get_or_init(|| true);
+            });
+        }
+
+        let report = tp.shutdown_now();
+        drop(release_worker);
+
+        assert_eq!(report.abandoned, 3, "not all queued jobs were abandoned");
+        assert_eq!(report.unfinished, 1, "the running job was not reported");
+        assert!(was_executed.get().is_none(), "a queued job was executed");
+    }
+
+    #[test]
+    fn submit_returns_the_result_of_the_job() {
+        let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+
+        let value = tp.submit(|| 6 * 7);
+        let panic = tp.submit(|| panic!("job panicked"));
+
+        assert_eq!(value.join().unwrap(), 42, "the value was not returned");
+        assert_eq!(
+            panic.join().unwrap_err().panic_message(),
+            Some("job panicked"),
+            "the panic was not reported"
+        );
+    }
+
+    #[test]
+    fn submit_reports_jobs_abandoned_by_shutdown() {
+        let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+        let release_worker = block_worker(&tp);
+
+        let handle = tp.submit(|| ());
+        let _ = tp.shutdown_now();
+        drop(release_worker);
+
+        assert!(
+            matches!(handle.join(), Err(TaskError::Abandoned)),
+            "the abandoned job was not reported"
+        );
+    }
+
+    #[test]
+    fn builder_uses_abort_panic_policy_by_default() {
+        let builder = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap());
+
+        assert_eq!(
+            builder.panic_policy,
+            PanicPolicy::Abort,
+            "the default panic policy is not abort"
+        );
+    }
+
+    #[test]
+    fn replaces_dead_workers_when_respawning_workers() {
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .panic_policy(PanicPolicy::RespawnWorker)
+            .build();
+
+        tp.execute(|| panic!());
+        let value = tp.submit(|| 42);
+
+        assert_eq!(value.join().unwrap(), 42, "the job after the panic failed");
+        assert_eq!(tp.restarts(), 1, "the dead worker was not replaced");
+
+        let worker_ids: Vec<_> = tp.workers.lock().unwrap().iter().map(Worker::id).collect();
+        assert_eq!(worker_ids, [1], "the new worker did not get a fresh id");
+
+        assert_eq!(
+            tp.shutdown(Instant::now() + Duration::from_secs(60)),
+            ShutdownReport {
+                completed: 1,
+                abandoned: 0,
+                unfinished: 0
+            },
+            "the panicked job was counted as completed"
+        );
+    }
+
+    #[test]
+    fn keeps_executing_jobs_after_a_panic_when_catching_panics() {
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .panic_policy(PanicPolicy::CatchAndContinue)
+            .build();
+
+        tp.execute(|| panic!());
+        let value = tp.submit(|| 42);
+
+        assert_eq!(value.join().unwrap(), 42, "the job after the panic failed");
+        assert_eq!(tp.restarts(), 0, "a worker was replaced");
+        assert_eq!(
+            tp.shutdown(Instant::now() + Duration::from_secs(60)),
+            ShutdownReport {
+                completed: 1,
+                abandoned: 0,
+                unfinished: 0
+            },
+            "the panicked job was counted as completed"
+        );
+    }
+
+    #[test]
+    fn try_execute_gives_the_job_back_when_the_queue_is_full() {
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .queue_capacity(1)
+            .build();
+
+        let release_worker = block_worker(&tp);
+
+        assert!(tp.try_execute(|| {}).is_ok(), "the queue was full too soon");
+
+        let was_executed = Arc::new(OnceLock::new());
+        let was_executed_clone = Arc::clone(&was_executed);
+
+        let result = tp.try_execute(move || {
+            was_executed_clone.get_or_init(|| true);
+        });
+
+        assert!(result.is_err(), "the queue was not full");
+
+        result.unwrap_err().into_job()();
+        assert!(was_executed.get().is_some(), "the job given back was wrong");
+
+        drop(release_worker);
+    }
+
+    #[test]
+    fn execute_timeout_waits_for_room_in_the_queue() {
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .queue_capacity(1)
+            .build();
+
+        let release_worker = block_worker(&tp);
+        tp.execute(|| {});
+
+        assert!(
+            tp.execute_timeout(|| {}, Duration::from_millis(10))
+                .is_err(),
+            "the queue was not full"
+        );
+
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            drop(release_worker);
+        });
+
+        assert!(
+            tp.execute_timeout(|| {}, Duration::from_secs(60)).is_ok(),
+            "the job was not queued after room was made in the queue"
+        );
+
+        releaser.join().unwrap();
+    }
+
+    // waits until the pool has `count` workers, for at most a few seconds
+    fn wait_for_worker_count(tp: &ThreadPool, count: usize) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+
+        while Instant::now() < deadline {
+            if tp.workers.lock().unwrap().len() == count {
+                return true;
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        false
+    }
+
+    #[test]
+    fn set_size_spawns_and_retires_workers() {
+        let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+
+        tp.set_size(NonZeroUsize::new(4usize).unwrap());
+
+        assert_eq!(tp.size(), 4, "the size was not changed");
+        assert_eq!(
+            tp.workers.lock().unwrap().len(),
+            4,
+            "no workers were spawned"
+        );
+
+        tp.set_size(NonZeroUsize::new(1usize).unwrap());
+
+        assert_eq!(tp.size(), 1, "the size was not changed");
+        assert!(
+            wait_for_worker_count(&tp, 1),
+            "the workers were not retired"
+        );
+
+        let value = tp.submit(|| 42);
+        assert_eq!(value.join().unwrap(), 42, "the remaining worker failed");
+    }
+
+    #[test]
+    fn grows_when_busy_and_shrinks_when_idle() {
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .max_size(NonZeroUsize::new(3usize).unwrap())
+            .idle_timeout(Duration::from_millis(50))
+            .build();
+
+        let release_workers: Vec<_> = (0..3).map(|_| block_worker(&tp)).collect();
+
+        assert_eq!(tp.size(), 3, "the pool did not grow");
+
+        let value = tp.submit(|| 42);
+        assert_eq!(tp.size(), 3, "the pool grew past its maximum size");
+
+        drop(release_workers);
+
+        assert_eq!(value.join().unwrap(), 42, "the queued job failed");
+        assert!(
+            wait_for_worker_count(&tp, 1),
+            "the idle workers were not retired"
+        );
+        assert_eq!(tp.size(), 1, "the pool shrank below its starting size");
+    }
+
+    #[test]
+    fn stats_describe_what_the_pool_is_doing() {
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .panic_policy(PanicPolicy::CatchAndContinue)
+            .build();
+
+        tp.execute(|| panic!());
+        let release_worker = block_worker(&tp);
+
+        for _ in 0..2 {
+            tp.execute(|| {});
+        }
+
+        let stats = tp.stats();
+        drop(release_worker);
+
+        assert_eq!(stats.queued_jobs, 2, "the queued jobs were not counted");
+        assert_eq!(stats.busy_workers, 1, "the busy worker was not counted");
+        assert_eq!(stats.idle_workers, 0, "an idle worker was counted");
+        assert_eq!(stats.completed_jobs, 0, "a job was counted as completed");
+        assert_eq!(stats.panicked_jobs, 1, "the panicked job was not counted");
+        assert_eq!(
+            stats.queue_wait.count(),
+            2,
+            "the queue wait of the started jobs was not recorded"
+        );
+        assert_eq!(
+            stats.run_time.count(),
+            1,
+            "the run time of the finished jobs was not recorded"
+        );
+    }
+
+    #[test]
+    fn gives_events_to_the_logger() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let events_clone = Arc::clone(&events);
+
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .logger(move |event: &PoolEvent| events_clone.lock().unwrap().push(event.clone()))
+            .build();
+
+        tp.execute(|| {});
+        drop(tp);
+
+        let events: Vec<_> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| (event.level(), event.worker_id(), event.job_id()))
+            .collect();
+
+        assert_eq!(
+            events,
+            [
+                (LogLevel::Info, 0, None),
+                (LogLevel::Debug, 0, Some(0)),
+                (LogLevel::Debug, 0, Some(0)),
+                (LogLevel::Info, 0, None),
+            ],
+            "the events were not given to the logger"
+        );
+    }
+
+    #[test]
+    fn spawns_threads_with_the_thread_settings() {
+        let started_workers = Arc::new(Mutex::new(Vec::new()));
+        let stopped_workers = Arc::new(Mutex::new(Vec::new()));
+        let started_workers_clone = Arc::clone(&started_workers);
+        let stopped_workers_clone = Arc::clone(&stopped_workers);
+
+        let tp = ThreadPoolBuilder::new(NonZeroUsize::new(1usize).unwrap())
+            .thread_name_prefix("test-worker-")
+            .stack_size(8 * 1024 * 1024)
+            .on_thread_start(move |id| started_workers_clone.lock().unwrap().push(id))
+            .on_thread_stop(move |id| stopped_workers_clone.lock().unwrap().push(id))
+            .build();
+
+        let thread_name = tp.submit(|| thread::current().name().map(String::from));
+
+        // more than the default stack size of 2 MiB
+        let stack_usage = tp.submit(|| {
+            #[allow(clippy::large_stack_arrays)] // the point is to use a lot of stack
+            let buffer = [1u8; 4 * 1024 * 1024];
+            hint::black_box(&buffer)
+                .iter()
+                .map(|&b| usize::from(b))
+                .sum::<usize>()
+        });
+
+        assert_eq!(
+            thread_name.join().unwrap().as_deref(),
+            Some("test-worker-0"),
+            "the thread was not named"
+        );
+        assert_eq!(
+            stack_usage.join().unwrap(),
+            4 * 1024 * 1024,
+            "the job did not get a big enough stack"
+        );
+
+        drop(tp);
+
+        assert_eq!(
+            *started_workers.lock().unwrap(),
+            [0],
+            "on_thread_start was not called"
+        );
+        assert_eq!(
+            *stopped_workers.lock().unwrap(),
+            [0],
+            "on_thread_stop was not called"
+        );
+    }
+
+    #[test]
+    fn runs_queued_jobs_by_priority_and_skips_cancelled_ones() {
+        let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+        let release_worker = block_worker(&tp);
+
+        let (order_sender, order_receiver) = mpsc::channel();
+        let token = CancellationToken::new();
+
+        for (name, priority) in [("low", Priority::Low), ("high", Priority::High)] {
+            let order_sender = order_sender.clone();
+            tp.execute_with_priority(move || order_sender.send(name).unwrap(), priority);
+        }
+
+        tp.execute_cancellable(
+            move || order_sender.send("cancelled").unwrap(),
+            Priority::High,
+            &token,
+        );
+
+        token.cancel();
+        drop(release_worker);
+
+        // queued last with the lowest priority, so it is executed last
+        let (done_sender, done_receiver) = mpsc::channel();
+        tp.execute_with_priority(move || done_sender.send(()).unwrap(), Priority::Low);
+        done_receiver.recv().unwrap();
+
+        assert_eq!(
+            order_receiver.try_iter().collect::<Vec<_>>(),
+            ["high", "low"],
+            "the jobs were not executed by priority"
+        );
+        assert_eq!(
+            tp.stats().cancelled_jobs,
+            1,
+            "the cancelled job was not counted"
+        );
+    }
+
+    #[test]
+    fn scope_lets_jobs_borrow_data() {
+        let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+
+        let numbers = [1, 2, 3, 4];
+        let sum = AtomicUsize::new(0);
+
+        let returned = tp.scope(|s| {
+            for number in &numbers {
+                s.execute(|| {
+                    // nested jobs can borrow the same data
+                    s.execute(|| {
+                        sum.fetch_add(*number, Ordering::SeqCst);
+                    });
+                });
+            }
+
+            "done"
+        });
+
+        assert_eq!(returned, "done", "the result of the scope was not returned");
+        assert_eq!(
+            sum.load(Ordering::SeqCst),
+            10,
+            "not all jobs were done when the scope returned"
+        );
+    }
+
+    #[test]
+    fn scope_panics_after_the_jobs_are_done_when_a_job_panics() {
+        let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+        let finished_jobs = AtomicUsize::new(0);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            tp.scope(|s| {
+                s.execute(|| panic!("scoped job panicked"));
+
+                s.execute(|| {
+                    thread::sleep(Duration::from_millis(10));
+                    finished_jobs.fetch_add(1, Ordering::SeqCst);
+                });
+            });
+        }));
+
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<&str>(),
+            Some(&"scoped job panicked"),
+            "the panic of the job was not propagated"
+        );
+        assert_eq!(
+            finished_jobs.load(Ordering::SeqCst),
+            1,
+            "the scope did not wait for the other job"
+        );
+    }
+
+    #[test]
+    fn execute_after_runs_the_job_after_the_delay() {
+        let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+        let (sender, receiver) = mpsc::channel();
+        let cancelled_sender = sender.clone();
+
+        let scheduled_at = Instant::now();
+        tp.execute_after(Duration::from_millis(50), move || {
+            sender.send("delayed").unwrap();
+        });
+
+        tp.execute_after(Duration::from_millis(10), move || {
+            cancelled_sender.send("cancelled").unwrap();
+        })
+        .cancel();
+
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_secs(5)),
+            Ok("delayed"),
+            "the delayed job was not executed"
+        );
+        assert!(
+            scheduled_at.elapsed() >= Duration::from_millis(50),
+            "the delayed job was executed too early"
+        );
+        assert!(receiver.recv().is_err(), "the cancelled job was executed");
+    }
+
+    #[test]
+    fn execute_every_repeats_the_job_until_cancelled() {
+        let tp = ThreadPool::build(NonZeroUsize::new(1usize).unwrap());
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+
+        let handle = tp.execute_every(Duration::from_millis(5), move || {
+            // fails once the receiver is gone after the test
+            let _ = sender.lock().unwrap().send(());
+        });
+
+        for _ in 0..3 {
+            receiver
+                .recv_timeout(Duration::from_secs(5))
+                .expect("the periodic job was not repeated");
+        }
+
+        handle.cancel();
+
+        // a run that was already queued can still come in
+        thread::sleep(Duration::from_millis(50));
+        receiver.try_iter().for_each(drop);
+        thread::sleep(Duration::from_millis(50));
+
+        assert!(
+            receiver.try_recv().is_err(),
+            "the periodic job was executed after being cancelled"
+        );
+    }
 }
@@ -0,0 +1,293 @@
+use std::{
+    fmt::{self, Debug, Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+/// How important a [`PoolEvent`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// Happens for every job, so it is very frequent under load.
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Something that happened in a [`ThreadPool`](crate::ThreadPool),
+/// given to the [`Logger`] of the pool.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PoolEvent {
+    WorkerStarted {
+        worker_id: usize,
+    },
+    JobStarted {
+        worker_id: usize,
+        job_id: u64,
+        /// How long the job waited in the queue.
+        queue_wait: Duration,
+    },
+    JobFinished {
+        worker_id: usize,
+        job_id: u64,
+        run_time: Duration,
+    },
+    JobPanicked {
+        worker_id: usize,
+        job_id: u64,
+        run_time: Duration,
+    },
+    /// The worker exited because it was idle for too long
+    /// or because the pool was made smaller.
+    WorkerRetired {
+        worker_id: usize,
+    },
+    /// The worker exited because the pool is shutting down.
+    WorkerStopped {
+        worker_id: usize,
+    },
+    /// The thread of the worker died because of a panic.
+    WorkerDied {
+        worker_id: usize,
+    },
+    WorkerReplaced {
+        worker_id: usize,
+        new_worker_id: usize,
+    },
+    /// The worker was still busy when the shutdown deadline was
+    /// reached, so its thread was left to finish on its own.
+    WorkerDetached {
+        worker_id: usize,
+    },
+}
+
+impl PoolEvent {
+    #[must_use]
+    pub fn level(&self) -> LogLevel {
+        match self {
+            PoolEvent::JobStarted { .. } | PoolEvent::JobFinished { .. } => LogLevel::Debug,
+            PoolEvent::WorkerStarted { .. }
+            | PoolEvent::WorkerRetired { .. }
+            | PoolEvent::WorkerStopped { .. } => LogLevel::Info,
+            PoolEvent::WorkerReplaced { .. } | PoolEvent::WorkerDetached { .. } => LogLevel::Warn,
+            PoolEvent::JobPanicked { .. } | PoolEvent::WorkerDied { .. } => LogLevel::Error,
+        }
+    }
+
+    /// Returns the id of the worker the event is about.
+    #[must_use]
+    pub fn worker_id(&self) -> usize {
+        match *self {
+            PoolEvent::WorkerStarted { worker_id }
+            | PoolEvent::JobStarted { worker_id, .. }
+            | PoolEvent::JobFinished { worker_id, .. }
+            | PoolEvent::JobPanicked { worker_id, .. }
+            | PoolEvent::WorkerRetired { worker_id }
+            | PoolEvent::WorkerStopped { worker_id }
+            | PoolEvent::WorkerDied { worker_id }
+            | PoolEvent::WorkerReplaced { worker_id, .. }
+            | PoolEvent::WorkerDetached { worker_id } => worker_id,
+        }
+    }
+
+    /// Returns the id of the job the event is about, if any.
+    #[must_use]
+    pub fn job_id(&self) -> Option<u64> {
+        match *self {
+            PoolEvent::JobStarted { job_id, .. }
+            | PoolEvent::JobFinished { job_id, .. }
+            | PoolEvent::JobPanicked { job_id, .. } => Some(job_id),
+            _ => None,
+        }
+    }
+
+    /// Returns the queue wait of a started job or
+    /// the run time of a finished job.
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        match *self {
+            PoolEvent::JobStarted { queue_wait, .. } => Some(queue_wait),
+            PoolEvent::JobFinished { run_time, .. } | PoolEvent::JobPanicked { run_time, .. } => {
+                Some(run_time)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for PoolEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolEvent::WorkerStarted { worker_id } => write!(f, "Worker {worker_id} started."),
+            PoolEvent::JobStarted {
+                worker_id,
+                job_id,
+                queue_wait,
+            } => write!(
+                f,
+                "Worker {worker_id} started job {job_id} after it waited {queue_wait:?}."
+            ),
+            PoolEvent::JobFinished {
+                worker_id,
+                job_id,
+                run_time,
+            } => write!(
+                f,
+                "Worker {worker_id} finished job {job_id} in {run_time:?}."
+            ),
+            PoolEvent::JobPanicked {
+                worker_id,
+                job_id,
+                run_time,
+            } => write!(
+                f,
+                "Job {job_id} panicked on worker {worker_id} after {run_time:?}."
+            ),
+            PoolEvent::WorkerRetired { worker_id } => write!(f, "Worker {worker_id} retired."),
+            PoolEvent::WorkerStopped { worker_id } => {
+                write!(f, "Worker {worker_id} disconnected; shutting down.")
+            }
+            PoolEvent::WorkerDied { worker_id } => {
+                write!(f, "The thread of worker {worker_id} died.")
+            }
+            PoolEvent::WorkerReplaced {
+                worker_id,
+                new_worker_id,
+            } => write!(
+                f,
+                "Replaced worker {worker_id} with worker {new_worker_id}."
+            ),
+            PoolEvent::WorkerDetached { worker_id } => write!(f, "Detached worker {worker_id}."),
+        }
+    }
+}
+
+/// Receives the [`PoolEvent`]s of a [`ThreadPool`](crate::ThreadPool),
+/// see [`ThreadPoolBuilder::logger`](crate::ThreadPoolBuilder::logger).
+///
+/// The events are given to the logger on the threads of the pool, so
+/// logging should be quick. Closures taking a `&PoolEvent` are loggers too.
+pub trait Logger: Send + Sync {
+    /// Returns `true` if events with `level` should be given to
+    /// [`Logger::log`]. By default, all events are.
+    fn enabled(&self, level: LogLevel) -> bool {
+        let _ = level;
+        true
+    }
+
+    fn log(&self, event: &PoolEvent);
+}
+
+impl<F> Logger for F
+where
+    F: Fn(&PoolEvent) + Send + Sync,
+{
+    fn log(&self, event: &PoolEvent) {
+        self(event);
+    }
+}
+
+/// A [`Logger`] that writes the events with at least
+/// a given [`LogLevel`] to the standard error.
+#[derive(Clone, Copy, Debug)]
+pub struct StderrLogger {
+    min_level: LogLevel,
+}
+
+impl StderrLogger {
+    #[must_use]
+    pub fn new(min_level: LogLevel) -> StderrLogger {
+        StderrLogger { min_level }
+    }
+}
+
+impl Logger for StderrLogger {
+    fn enabled(&self, level: LogLevel) -> bool {
+        level >= self.min_level
+    }
+
+    fn log(&self, event: &PoolEvent) {
+        eprintln!("[{}] {event}", event.level());
+    }
+}
+
+/// The optional [`Logger`] of a pool, shared by all of its threads.
+#[derive(Clone, Default)]
+pub struct SharedLogger(Option<Arc<dyn Logger>>);
+
+impl SharedLogger {
+    pub fn new(logger: impl Logger + 'static) -> SharedLogger {
+        SharedLogger(Some(Arc::new(logger)))
+    }
+
+    pub fn log(&self, event: &PoolEvent) {
+        if let Some(logger) = &self.0 {
+            if logger.enabled(event.level()) {
+                logger.log(event);
+            }
+        }
+    }
+}
+
+impl Debug for SharedLogger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() {
+            "SharedLogger(Some(..))"
+        } else {
+            "SharedLogger(None)"
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_describe_their_fields() {
+        let event = PoolEvent::JobFinished {
+            worker_id: 2,
+            job_id: 9,
+            run_time: Duration::from_millis(5),
+        };
+
+        assert_eq!(event.level(), LogLevel::Debug, "the level is different");
+        assert_eq!(event.worker_id(), 2, "the worker id is different");
+        assert_eq!(event.job_id(), Some(9), "the job id is different");
+        assert_eq!(
+            event.duration(),
+            Some(Duration::from_millis(5)),
+            "the duration is different"
+        );
+        assert_eq!(
+            event.to_string(),
+            "Worker 2 finished job 9 in 5ms.",
+            "the message is different"
+        );
+    }
+
+    #[test]
+    fn stderr_logger_filters_by_level() {
+        let logger = StderrLogger::new(LogLevel::Warn);
+
+        assert!(!logger.enabled(LogLevel::Info), "info events were enabled");
+        assert!(
+            logger.enabled(LogLevel::Error),
+            "error events were disabled"
+        );
+    }
+}
@@ -1,14 +1,34 @@
+mod access_log;
+mod compression;
+mod config;
+mod connection;
+mod http;
+mod router;
+mod signals;
+mod static_files;
+
 use std::{
-    fs,
-    io::{BufRead, BufReader, Write},
-    net::{TcpListener, TcpStream},
-    num::NonZeroUsize,
-    process::ExitCode,
+    env, fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    process::{self, ExitCode},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use web_server::ThreadPool;
+use access_log::{AccessLog, Entry};
+use config::{Config, USAGE};
+use connection::Connection;
+use http::{content_type, Request, RequestError, Response, Status};
+use router::Router;
+use signals::Signals;
+use static_files::{FileCache, StaticFiles};
+use web_server::{StderrLogger, ThreadPoolBuilder};
 
 fn main() -> ExitCode {
     if let Err(e) = execute() {
@@ -20,19 +40,119 @@ fn main() -> ExitCode {
 }
 
 fn execute() -> Result<(), String> {
-    let listener = TcpListener::bind("127.0.0.1:7878")
+    let args: Vec<_> = env::args().skip(1).collect();
+
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        print!("{USAGE}");
+        return Ok(());
+    }
+
+    let config = Config::from_args(args)
+        .map_err(|e| format!("Invalid configuration: {e}. Run with --help for the options."))?;
+
+    let signals = Signals::register()
+        .map_err(|e| format!("Could not register the shutdown signals: {e}."))?;
+
+    let listener = TcpListener::bind(config.socket_address())
         .map_err(|e| format!("Could not bind the socket: {e}."))?;
 
-    let pool = ThreadPool::build(NonZeroUsize::new(4usize).unwrap());
+    let local_address = listener
+        .local_addr()
+        .map_err(|e| format!("Could not get the address of the socket: {e}."))?;
+
+    // the port can be chosen by the system, so this is where to find the server
+    println!("Listening on {local_address}.");
+
+    let mut pool = ThreadPoolBuilder::new(config.workers)
+        .queue_capacity(config.queue_capacity)
+        .thread_name_prefix("web-server-worker-");
+
+    // the pool is silent unless asked otherwise
+    if let Some(level) = config.pool_log_level {
+        pool = pool.logger(StderrLogger::new(level));
+    }
+
+    let pool = pool.build();
+
+    let file_cache = config
+        .file_cache_size
+        .map(|capacity| Arc::new(FileCache::new(capacity)));
+
+    let static_files = StaticFiles::new(&config.document_root, file_cache.clone())
+        .map_err(|e| format!("Could not open the document root: {e}."))?;
+
+    // the pages of the server itself, which do not depend on the document root
+    let pages = StaticFiles::new("res", file_cache)
+        .map_err(|e| format!("Could not open the pages: {e}."))?;
+
+    let sleep_pages = pages.clone();
+
+    let access_log = config
+        .access_log
+        .as_deref()
+        .map(AccessLog::open)
+        .transpose()
+        .map_err(|e| format!("Could not open the access log: {e}."))?;
+
+    let mut router = Router::new(not_found)
+        .route("GET", "/", move |request: &Request| hello(&pages, request))
+        .route("GET", "/sleep", move |request: &Request| {
+            sleep(&sleep_pages, request)
+        })
+        .route("GET", "/hello/:name", greet)
+        .route("GET", "/static/*path", move |request: &Request| {
+            let path = request.param("path").unwrap_or_default();
+            serve_file(&static_files, path, request)
+        });
+
+    for (pattern, value) in &config.cache_control {
+        if !router.cache_control(pattern, value) {
+            return Err(format!(
+                "Invalid configuration: `cache_control` has no route `{pattern}`. \
+                 Run with --help for the options."
+            ));
+        }
+    }
+
+    let server = Arc::new(Server {
+        config,
+        router,
+        access_log,
+        open_connections: AtomicUsize::new(0),
+        shed_connections: AtomicUsize::new(0),
+        shutting_down: AtomicBool::new(false),
+    });
+
+    wait_for_shutdown_signals(signals, Arc::clone(&server), local_address);
+
+    for stream in listener.incoming() {
+        if server.shutting_down.load(Ordering::SeqCst) {
+            break;
+        }
 
-    for stream in listener.incoming().take(2) {
         match stream {
             Ok(stream) => {
-                pool.execute(|| {
-                    if let Err(e) = handle_connection(stream) {
+                let Some(open_connection) = OpenConnection::try_new(&server) else {
+                    shed_connection(&server, &stream);
+                    continue;
+                };
+
+                let overload_stream = stream
+                    .try_clone()
+                    .map_err(|e| format!("Could not clone the connection stream: {e}."));
+
+                let job = move || {
+                    if let Err(e) = handle_connection(stream, &open_connection.0) {
                         eprintln!("{e}");
                     }
-                });
+                };
+
+                if pool.try_execute(job).is_err() {
+                    match overload_stream {
+                        Ok(stream) => shed_connection(&server, &stream),
+                        Err(e) => eprintln!("{e}"),
+                    }
+                }
             }
             Err(e) => {
                 eprintln!("Could not listen for connection: {e}.");
@@ -41,46 +161,302 @@ fn execute() -> Result<(), String> {
     }
 
     println!("Shutting down.");
+
+    let report = pool.shutdown(Instant::now() + server.config.shutdown_timeout);
+    let unfinished_connections = report.abandoned + report.unfinished;
+
+    let shed_connections = server.shed_connections.load(Ordering::SeqCst);
+
+    if shed_connections > 0 {
+        eprintln!("Connections shed while overloaded: {shed_connections}.");
+    }
+
+    if unfinished_connections > 0 {
+        return Err(format!(
+            "Could not finish {unfinished_connections} connections before the shutdown timeout."
+        ));
+    }
+
     Ok(())
 }
 
-fn handle_connection(mut stream: TcpStream) -> Result<(), String> {
-    let timeout = Some(Duration::from_secs(5));
+// what all the connections share
+struct Server {
+    config: Config,
+    router: Router,
+    access_log: Option<AccessLog>,
+    open_connections: AtomicUsize,
+
+    // how many connections were answered with a 503 because the server was busy
+    shed_connections: AtomicUsize,
 
-    stream
-        .set_read_timeout(timeout)
-        .map_err(|e| format!("Could not set the read timeout: {e}."))?;
+    shutting_down: AtomicBool,
+}
 
-    stream
-        .set_write_timeout(timeout)
-        .map_err(|e| format!("Could not set the write timeout: {e}."))?;
+// stops the accept loop on the first signal and exits right away on the second one
+fn wait_for_shutdown_signals(mut signals: Signals, server: Arc<Server>, local_address: SocketAddr) {
+    // the accept loop is woken up by connecting to it
+    let wake_up_address = match local_address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => (Ipv4Addr::LOCALHOST, local_address.port()).into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => (Ipv6Addr::LOCALHOST, local_address.port()).into(),
+        _ => local_address,
+    };
 
-    let buf_reader = BufReader::new(&mut stream);
+    thread::spawn(move || {
+        if let Err(e) = signals.wait() {
+            eprintln!("Could not wait for the shutdown signals: {e}.");
+            return;
+        }
 
-    let request_line = buf_reader
-        .lines()
-        .next()
-        .ok_or("Request does not have a first line.")?
-        .map_err(|e| format!("Could not read the first line of the request: {e}."))?;
+        server.shutting_down.store(true, Ordering::SeqCst);
 
-    let (status_line, filename) = match &request_line[..] {
-        "GET / HTTP/1.1" => ("HTTP/1.1 200 OK", "res/hello.html"),
-        "GET /sleep HTTP/1.1" => {
-            thread::sleep(Duration::from_secs(5));
-            ("HTTP/1.1 200 OK", "res/hello.html")
+        if let Err(e) = TcpStream::connect(wake_up_address) {
+            eprintln!("Could not wake up the accept loop: {e}.");
         }
-        _ => ("HTTP/1.1 404 NOT FOUND", "res/404.html"),
-    };
 
-    let contents = fs::read_to_string(filename)
-        .map_err(|e| format!("Could not read file {filename}: {e}."))?;
+        if signals.wait().is_ok() {
+            eprintln!("Exiting without waiting for the connections.");
+            process::exit(1);
+        }
+    });
+}
+
+// counts a connection as open until it is dropped
+struct OpenConnection(Arc<Server>);
+
+impl OpenConnection {
+    // `None` if the server already has as many open connections as it may
+    fn try_new(server: &Arc<Server>) -> Option<OpenConnection> {
+        let max_connections = server.config.max_connections();
+
+        server
+            .open_connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open_connections| {
+                (open_connections < max_connections).then_some(open_connections + 1)
+            })
+            .ok()
+            .map(|_| OpenConnection(Arc::clone(server)))
+    }
+}
+
+impl Drop for OpenConnection {
+    fn drop(&mut self) {
+        self.0.open_connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn handle_connection(stream: TcpStream, server: &Server) -> Result<(), String> {
+    let remote_address = stream.peer_addr().ok().map(|address| address.ip());
+    let connection = Connection::new(stream);
+    let mut buf_reader = BufReader::new(&connection);
+
+    loop {
+        // an idle connection holds on to its thread, so it is not kept for long
+        connection.set_budget(Some(server.config.keep_alive_timeout));
+
+        // pipelined requests are already buffered, so they do not wait
+        match buf_reader.fill_buf() {
+            Ok([]) => return Ok(()),
+            Ok(_) => {}
+            Err(e) if connection::is_timeout(&e) => return Ok(()),
+            Err(e) => return Err(format!("Could not wait for a request: {e}.")),
+        }
+
+        // however slowly a client trickles its header in, it has to be done in time
+        connection.set_budget(Some(server.config.header_timeout));
+        let mut log_entry = Entry::new(remote_address);
+        let limits = &server.config.limits;
+
+        let request = match Request::read_head(&mut buf_reader, limits) {
+            Ok(Some(mut request)) => {
+                // the time needed by the handler to come up with the response is not counted
+                connection.set_budget(Some(server.config.request_timeout));
+
+                request
+                    .read_body(&mut buf_reader, limits)
+                    .map(|()| Some(request))
+            }
+            result => result,
+        };
+
+        let (mut response, keep_alive) = match request {
+            Ok(Some(request)) => {
+                let keep_alive = request.keep_alive();
+                log_entry.describe_request(&request);
+
+                let accept_encoding = request
+                    .headers
+                    .get("Accept-Encoding")
+                    .map(ToString::to_string);
+
+                let remaining_budget = connection.remaining_budget();
+
+                let mut response = server.router.handle(request);
+
+                if let Err(e) = server
+                    .config
+                    .compression
+                    .compress(accept_encoding.as_deref(), &mut response)
+                {
+                    eprintln!("Could not compress the response: {e}.");
+                    response = Response::new(Status::InternalServerError, Vec::new());
+                }
+
+                connection.set_budget(remaining_budget);
+
+                (response, keep_alive)
+            }
+            Ok(None) => return Ok(()),
+            // the rest of the connection cannot be trusted to be a request
+            Err(e) => match request_error_status(&e) {
+                Some(status) => {
+                    eprintln!("Rejected a request: {e}.");
+
+                    let response = match status {
+                        Status::BadRequest => file_response(status, "res/400.html"),
+                        // the budget is spent, but the client may still be listening
+                        Status::RequestTimeout => {
+                            connection.set_budget(Some(server.config.request_timeout));
+                            Response::new(status, Vec::new())
+                        }
+                        _ => Response::new(status, Vec::new()),
+                    };
+
+                    (response, false)
+                }
+                None => return Err(format!("Could not read the request: {e}.")),
+            },
+        };
+
+        let keep_alive = keep_alive && !server.shutting_down.load(Ordering::SeqCst);
+
+        if !keep_alive {
+            response.headers.add("Connection", "close");
+        }
+
+        let written = response.write_to(&mut &connection);
+        log_entry.finish(&response, written.is_ok());
+
+        log_request(server, &log_entry);
+        written.map_err(|e| format!("Could not write response: {e}."))?;
+
+        if !keep_alive {
+            return Ok(());
+        }
+    }
+}
+
+// a client too slow to send its request is told so, unlike one whose connection failed
+fn request_error_status(e: &RequestError) -> Option<Status> {
+    match e {
+        RequestError::Io(e) if connection::is_timeout(e) => Some(Status::RequestTimeout),
+        e => e.status(),
+    }
+}
+
+fn hello(pages: &StaticFiles, request: &Request) -> Response {
+    serve_file(pages, "hello.html", request)
+}
+
+fn sleep(pages: &StaticFiles, request: &Request) -> Response {
+    thread::sleep(Duration::from_secs(5));
+    serve_file(pages, "hello.html", request)
+}
+
+fn greet(request: &Request) -> Response {
+    let name = request.param("name").unwrap_or_default();
+
+    // plain text, so the name cannot inject any markup
+    let mut response = Response::new(Status::Ok, format!("Hello, {name}!"));
+    response
+        .headers
+        .add("Content-Type", "text/plain; charset=utf-8");
+
+    response
+}
+
+fn serve_file(static_files: &StaticFiles, path: &str, request: &Request) -> Response {
+    static_files
+        .serve(path, &request.headers)
+        .unwrap_or_else(|status| match status {
+            Status::NotFound => not_found(request),
+            _ => Response::new(status, Vec::new()),
+        })
+}
+
+fn not_found(_: &Request) -> Response {
+    file_response(Status::NotFound, "res/404.html")
+}
+
+fn file_response(status: Status, filename: &str) -> Response {
+    match fs::read(filename) {
+        Ok(contents) => {
+            let mut response = Response::new(status, contents);
+            response
+                .headers
+                .add("Content-Type", content_type(Path::new(filename)));
+
+            response
+        }
+        Err(e) => {
+            eprintln!("Could not read file {filename}: {e}.");
+            Response::new(Status::InternalServerError, Vec::new())
+        }
+    }
+}
+
+fn log_request(server: &Server, log_entry: &Entry) {
+    if let Some(access_log) = &server.access_log {
+        if let Err(e) = access_log.log(log_entry) {
+            eprintln!("Could not write to the access log: {e}.");
+        }
+    }
+}
+
+// answers with a 503 right away instead of queueing the connection behind the busy workers
+fn shed_connection(server: &Server, stream: &TcpStream) {
+    server.shed_connections.fetch_add(1, Ordering::SeqCst);
+
+    let mut log_entry = Entry::new(stream.peer_addr().ok().map(|address| address.ip()));
+
+    let mut response = Response::new(Status::ServiceUnavailable, Vec::new());
+    response.headers.add(
+        "Retry-After",
+        server.config.retry_after.as_secs().to_string(),
+    );
+    response.headers.add("Connection", "close");
+
+    let written = write_rejection(stream, &response);
+    log_entry.finish(&response, written.is_ok());
+    log_request(server, &log_entry);
+
+    if let Err(e) = written {
+        eprintln!("Could not shed a connection: {e}.");
+    }
+}
+
+// the accept loop writes the rejections, so it must not wait for the clients:
+// the response is small enough for the send buffer of a new connection,
+// and the ones it does not fit in are given up on
+fn write_rejection(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    response.write_to(&mut bytes)?;
+
+    stream.set_nonblocking(true)?;
+
+    if stream.write(&bytes)? < bytes.len() {
+        return Err(io::Error::other(
+            "the response does not fit in the send buffer",
+        ));
+    }
 
-    let length = contents.len();
-    let response = format!("{status_line}\r\nContent-Length: {length}\r\n\r\n{contents}");
+    stream.shutdown(Shutdown::Write)?;
 
-    stream
-        .write_all(response.as_bytes())
-        .map_err(|e| format!("Could not write response: {e}."))?;
+    // closing a connection with unread data resets it, which could lose the
+    // response. Only the data that already arrived is read, since waiting for
+    // the rest would hold up the accept loop, so the request of a client that
+    // was still sending it can be met with a reset instead of the response.
+    let _ = io::copy(&mut stream.take(64 * 1024), &mut io::sink());
 
     Ok(())
 }
@@ -0,0 +1,17 @@
+/// Decides what happens when a job executed by a
+/// [`ThreadPool`](crate::ThreadPool) panics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// The whole process is aborted.
+    #[default]
+    Abort,
+
+    /// The thread of the worker that executed the job is allowed to unwind
+    /// and a new worker, with a new id and a new thread, takes its place.
+    /// This way, the thread-local state of the panicking thread is not reused.
+    RespawnWorker,
+
+    /// The panic is caught with [`std::panic::catch_unwind`], logged, and
+    /// the same thread goes on to execute the next job.
+    CatchAndContinue,
+}
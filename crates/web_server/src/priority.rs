@@ -0,0 +1,22 @@
+/// The lane in which a job waits for a free thread, see
+/// [`ThreadPool::execute_with_priority`](crate::ThreadPool::execute_with_priority).
+///
+/// Queued jobs with a higher priority are executed before queued jobs
+/// with a lower priority. Jobs with the same priority are executed
+/// roughly in the order in which they were given to the pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// All the priorities, from the highest to the lowest.
+    pub(crate) const ALL: [Priority; 3] = [Priority::High, Priority::Normal, Priority::Low];
+
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
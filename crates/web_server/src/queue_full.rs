@@ -0,0 +1,42 @@
+use crate::types_traits::Job;
+
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+/// Returned by [`ThreadPool::try_execute`](crate::ThreadPool::try_execute)
+/// and [`ThreadPool::execute_timeout`](crate::ThreadPool::execute_timeout)
+/// when the job queue of the pool is full. Contains the job that
+/// could not be queued.
+pub struct QueueFullError<F>(F);
+
+impl<F> QueueFullError<F> {
+    /// Returns the job that could not be queued.
+    pub fn into_job(self) -> F {
+        self.0
+    }
+}
+
+impl<F> Debug for QueueFullError<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueFullError").finish_non_exhaustive()
+    }
+}
+
+impl<F> Display for QueueFullError<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "the job queue is full")
+    }
+}
+
+impl<F> Error for QueueFullError<F> {}
+
+/// Recovers the job given to the pool from a [`Job`] that could not be queued.
+pub fn queue_full_error<F: 'static>(job: Job) -> QueueFullError<F> {
+    QueueFullError(
+        *job.into_any()
+            .downcast::<F>()
+            .expect("job should have the type of the closure given to the pool"),
+    )
+}
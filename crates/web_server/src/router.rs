@@ -0,0 +1,334 @@
+use crate::http::{percent_decode, Request, Response, Status};
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+};
+
+/// Answers a [`Request`] that matched a route of a [`Router`]. Plain
+/// functions taking a `&Request` and returning a [`Response`] are handlers.
+pub type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync>;
+
+#[derive(Debug)]
+enum Segment {
+    Literal(String),
+    Param(String),
+
+    // the rest of the path, which can span many segments
+    Wildcard(String),
+}
+
+struct Route {
+    method: String,
+    pattern: String,
+    segments: Vec<Segment>,
+    handler: Handler,
+    cache_control: Option<String>,
+}
+
+impl Route {
+    // returns the decoded parameters if the path matches the pattern
+    fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        let mut params = HashMap::new();
+        let mut path_segments = path.split('/').skip(1);
+
+        for segment in &self.segments {
+            let path_segment = path_segments.next()?;
+
+            match segment {
+                Segment::Literal(literal) => {
+                    if literal != path_segment {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    if path_segment.is_empty() {
+                        return None;
+                    }
+
+                    params.insert(name.clone(), percent_decode(path_segment)?);
+                }
+                Segment::Wildcard(name) => {
+                    let rest = path_segments.fold(path_segment.to_string(), |rest, segment| {
+                        rest + "/" + segment
+                    });
+
+                    params.insert(name.clone(), percent_decode(&rest)?);
+                    return Some(params);
+                }
+            }
+        }
+
+        path_segments.next().is_none().then_some(params)
+    }
+}
+
+/// Finds the [`Handler`] of a [`Request`] by its method and path.
+///
+/// Patterns are paths whose segments can be parameters, like
+/// `/users/:id`, which matches `/users/42` with the `id` parameter
+/// set to `42`. The last segment can be a wildcard, like `/files/*path`,
+/// which matches `/files/docs/a.txt` with the `path` parameter set to
+/// `docs/a.txt`. Routes are tried in the order they were added.
+pub struct Router {
+    routes: Vec<Route>,
+    not_found: Handler,
+}
+
+impl Router {
+    /// Creates a [`Router`] without routes, which
+    /// answers every request with `not_found`.
+    pub fn new<F>(not_found: F) -> Router
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        Router {
+            routes: Vec::new(),
+            not_found: Box::new(not_found),
+        }
+    }
+
+    /// Adds a route for requests with `method` whose paths match `pattern`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pattern` does not start with a `/`, if one of its
+    /// parameters does not have a name or if it has a wildcard
+    /// that is not its last segment.
+    #[must_use]
+    pub fn route<F>(mut self, method: &str, pattern: &str, handler: F) -> Router
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        assert!(pattern.starts_with('/'), "pattern should start with a '/'");
+
+        let segments: Vec<_> = pattern
+            .split('/')
+            .skip(1)
+            .map(|segment| {
+                if let Some(name) = segment.strip_prefix(':') {
+                    assert!(!name.is_empty(), "parameter should have a name");
+                    Segment::Param(name.to_string())
+                } else if let Some(name) = segment.strip_prefix('*') {
+                    assert!(!name.is_empty(), "wildcard should have a name");
+                    Segment::Wildcard(name.to_string())
+                } else {
+                    Segment::Literal(segment.to_string())
+                }
+            })
+            .collect();
+
+        let wildcard_position = segments
+            .iter()
+            .position(|segment| matches!(segment, Segment::Wildcard(_)));
+
+        assert!(
+            wildcard_position.map_or(true, |position| position == segments.len() - 1),
+            "wildcard should be the last segment"
+        );
+
+        self.routes.push(Route {
+            method: method.to_string(),
+            pattern: pattern.to_string(),
+            segments,
+            handler: Box::new(handler),
+            cache_control: None,
+        });
+
+        self
+    }
+
+    /// Gives the successful, 206 and 304 responses of the routes with `pattern`
+    /// a `Cache-Control` set to `value`, unless their handlers set one.
+    /// Returns `false` if there is no such route.
+    pub fn cache_control(&mut self, pattern: &str, value: &str) -> bool {
+        let mut found = false;
+
+        for route in self
+            .routes
+            .iter_mut()
+            .filter(|route| route.pattern == pattern)
+        {
+            route.cache_control = Some(value.to_string());
+            found = true;
+        }
+
+        found
+    }
+
+    /// Answers `request` with the handler of the first matching route.
+    ///
+    /// If the path matches some routes but none of them has the method of
+    /// the request, the response is a 405 listing the allowed methods.
+    pub fn handle(&self, mut request: Request) -> Response {
+        let mut allowed_methods = Vec::new();
+
+        for route in &self.routes {
+            let Some(params) = route.match_path(&request.path) else {
+                continue;
+            };
+
+            if route.method == request.method {
+                request.params = params;
+                let mut response = (route.handler)(&request);
+
+                if let Some(cache_control) = &route.cache_control {
+                    if matches!(
+                        response.status,
+                        Status::Ok | Status::PartialContent | Status::NotModified
+                    ) && !response.headers.contains("Cache-Control")
+                    {
+                        response.headers.add("Cache-Control", cache_control);
+                    }
+                }
+
+                return response;
+            }
+
+            if !allowed_methods.contains(&route.method.as_str()) {
+                allowed_methods.push(route.method.as_str());
+            }
+        }
+
+        if allowed_methods.is_empty() {
+            return (self.not_found)(&request);
+        }
+
+        let mut response = Response::new(Status::MethodNotAllowed, Vec::new());
+        response.headers.add("Allow", allowed_methods.join(", "));
+        response
+    }
+}
+
+impl Debug for Router {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let routes: Vec<_> = self
+            .routes
+            .iter()
+            .map(|route| (&route.method, &route.segments))
+            .collect();
+
+        f.debug_struct("Router")
+            .field("routes", &routes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{Body, Limits};
+
+    fn request(method: &str, path: &str) -> Request {
+        let input = format!("{method} {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        Request::read_head(&mut input.as_bytes(), &Limits::default())
+            .unwrap()
+            .unwrap()
+    }
+
+    fn not_found(_: &Request) -> Response {
+        Response::new(Status::NotFound, "not found")
+    }
+
+    fn show_user(request: &Request) -> Response {
+        Response::new(Status::Ok, format!("user {}", request.param("id").unwrap()))
+    }
+
+    fn update_user(_: &Request) -> Response {
+        Response::new(Status::Ok, "updated")
+    }
+
+    fn router() -> Router {
+        Router::new(not_found)
+            .route("GET", "/users/:id", show_user)
+            .route("PUT", "/users/:id", update_user)
+            .route("POST", "/users", update_user)
+    }
+
+    #[test]
+    fn gives_path_parameters_to_the_handler() {
+        let response = router().handle(request("GET", "/users/jane%20doe"));
+
+        assert_eq!(response.status, Status::Ok, "the route was not matched");
+        assert!(
+            matches!(&response.body, Body::Bytes(body) if body == b"user jane doe"),
+            "the parameter was not decoded"
+        );
+    }
+
+    #[test]
+    fn gives_the_rest_of_the_path_to_a_wildcard() {
+        let router = Router::new(not_found).route("GET", "/files/*path", |request: &Request| {
+            Response::new(Status::Ok, request.param("path").unwrap())
+        });
+
+        let response = router.handle(request("GET", "/files/docs/read%20me.txt"));
+
+        assert!(
+            matches!(&response.body, Body::Bytes(body) if body == b"docs/read me.txt"),
+            "the rest of the path was not given to the wildcard"
+        );
+    }
+
+    #[test]
+    fn answers_with_405_when_only_the_path_matches() {
+        let response = router().handle(request("DELETE", "/users/42"));
+
+        assert_eq!(
+            response.status,
+            Status::MethodNotAllowed,
+            "the method was not rejected"
+        );
+        assert_eq!(
+            response.headers.get("Allow"),
+            Some("GET, PUT"),
+            "the allowed methods are different"
+        );
+    }
+
+    #[test]
+    fn answers_with_not_found_when_no_path_matches() {
+        for path in ["/users/42/posts", "/users/", "/"] {
+            assert_eq!(
+                router().handle(request("GET", path)).status,
+                Status::NotFound,
+                "{path} was matched"
+            );
+        }
+    }
+
+    #[test]
+    fn sets_the_cache_control_of_successful_responses() {
+        let mut router = router();
+
+        assert!(
+            router.cache_control("/users/:id", "max-age=60"),
+            "the routes of the pattern were not found"
+        );
+        assert!(
+            !router.cache_control("/posts", "no-store"),
+            "a missing route was found"
+        );
+
+        for method in ["GET", "PUT"] {
+            assert_eq!(
+                router
+                    .handle(request(method, "/users/42"))
+                    .headers
+                    .get("Cache-Control"),
+                Some("max-age=60"),
+                "the {method} route does not have the Cache-Control"
+            );
+        }
+
+        for (method, path) in [("POST", "/users"), ("DELETE", "/users/42")] {
+            assert!(
+                !router
+                    .handle(request(method, path))
+                    .headers
+                    .contains("Cache-Control"),
+                "{method} {path} has a Cache-Control"
+            );
+        }
+    }
+}
@@ -0,0 +1,818 @@
+use crate::{cancellation::CancellationToken, priority::Priority, types_traits::Job};
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::{self, Debug, Formatter},
+    mem, ptr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, MutexGuard, RwLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// one queue for every priority, see Priority::index
+type Deque = Mutex<[VecDeque<QueuedJob>; 3]>;
+
+const YIELDS_BEFORE_SLEEPING: u32 = 4;
+
+thread_local! {
+    // the scheduler (by address) and the local queue of the worker running on this thread
+    static CURRENT_QUEUE: RefCell<Option<(usize, Arc<Deque>)>> = const { RefCell::new(None) };
+}
+
+/// Distributes jobs to the workers of a pool.
+///
+/// Every worker has its own local queue. Jobs given to the pool from
+/// outside are spread over the local queues, while jobs given to the pool
+/// by one of its workers go to the local queue of that worker. A worker
+/// takes jobs from its own queue first and steals from the queues of the
+/// other workers when its own queue is empty, so the workers rarely
+/// contend on the same lock. Jobs with a higher priority are looked
+/// for everywhere before jobs with a lower priority.
+pub struct Scheduler {
+    // used when no worker is registered and for the
+    // leftover jobs of the workers that exit
+    global: Deque,
+    locals: RwLock<Vec<Arc<Deque>>>,
+    next_local: AtomicUsize,
+
+    // counts the jobs that are in a queue or about to be put in one,
+    // in total and for every priority
+    queued: AtomicUsize,
+    queued_by_priority: [AtomicUsize; 3],
+    capacity: Option<usize>,
+    next_job_id: AtomicU64,
+
+    // the sleeping workers that were not woken up yet, and the
+    // wakeups that were sent but not received yet
+    sleeping: AtomicUsize,
+    sleep_lock: Mutex<usize>,
+    job_available: Condvar,
+
+    taken_jobs: Mutex<usize>,
+    job_taken: Condvar,
+
+    // the number of workers that were asked to exit
+    retiring: AtomicUsize,
+
+    closed: AtomicBool,
+    discard_jobs: AtomicBool,
+    abandoned_jobs: AtomicUsize,
+    cancelled_jobs: AtomicUsize,
+}
+
+/// How a job is queued.
+#[derive(Clone, Debug, Default)]
+pub struct JobOptions {
+    pub priority: Priority,
+    pub token: Option<CancellationToken>,
+}
+
+/// A job together with its id and the moment it was queued.
+pub struct QueuedJob {
+    pub id: u64,
+    pub job: Job,
+    pub queued_at: Instant,
+    token: Option<CancellationToken>,
+}
+
+impl QueuedJob {
+    fn is_cancelled(&self) -> bool {
+        self.token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+/// What a worker should do next, see [`Scheduler::next_job`].
+pub enum Next {
+    Job(QueuedJob),
+    /// No job arrived before the idle timeout.
+    Idle,
+    /// The worker was asked to exit, see [`Scheduler::retire_workers`].
+    Retire,
+    Closed,
+}
+
+impl Debug for Scheduler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("queued", &self.queued)
+            .field("capacity", &self.capacity)
+            .field("sleeping", &self.sleeping)
+            .field("retiring", &self.retiring)
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Scheduler {
+    /// Creates a new [`Scheduler`] that holds at most `capacity` jobs
+    /// that are not being executed, or any number of jobs without a capacity.
+    pub fn new(capacity: Option<usize>) -> Scheduler {
+        Scheduler {
+            global: Mutex::default(),
+            locals: RwLock::new(Vec::new()),
+            next_local: AtomicUsize::new(0),
+            queued: AtomicUsize::new(0),
+            queued_by_priority: Default::default(),
+            capacity,
+            next_job_id: AtomicU64::new(0),
+            sleeping: AtomicUsize::new(0),
+            sleep_lock: Mutex::new(0),
+            job_available: Condvar::new(),
+            taken_jobs: Mutex::new(0),
+            job_taken: Condvar::new(),
+            retiring: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+            discard_jobs: AtomicBool::new(false),
+            abandoned_jobs: AtomicUsize::new(0),
+            cancelled_jobs: AtomicUsize::new(0),
+        }
+    }
+
+    fn address(&self) -> usize {
+        ptr::from_ref(self) as usize
+    }
+
+    /// Gives the calling thread a local queue. The queue is
+    /// removed when the returned [`Registration`] is dropped.
+    pub fn register(&self) -> Registration<'_> {
+        let queue = Arc::new(Mutex::default());
+
+        self.locals
+            .write()
+            .expect("locals lock should not be poisoned")
+            .push(Arc::clone(&queue));
+
+        CURRENT_QUEUE.with(|current| {
+            *current.borrow_mut() = Some((self.address(), Arc::clone(&queue)));
+        });
+
+        Registration {
+            scheduler: self,
+            queue,
+        }
+    }
+
+    /// Queues a job, blocking while the scheduler is at capacity.
+    pub fn push(&self, job: Job, options: &JobOptions) {
+        let mut job = job;
+
+        loop {
+            // read before trying so that no job taken in between is missed
+            let taken_jobs = self.taken_jobs();
+
+            match self.try_push(job, options) {
+                Ok(()) => return,
+                Err(rejected_job) => job = rejected_job,
+            }
+
+            self.wait_for_taken_job(taken_jobs, None);
+        }
+    }
+
+    /// Queues a job, blocking while the scheduler is at capacity but at most
+    /// until `deadline` is reached. Gives the job back if it could not be queued.
+    pub fn push_until(&self, job: Job, options: &JobOptions, deadline: Instant) -> Result<(), Job> {
+        let mut job = job;
+
+        loop {
+            let taken_jobs = self.taken_jobs();
+
+            match self.try_push(job, options) {
+                Ok(()) => return Ok(()),
+                Err(rejected_job) => job = rejected_job,
+            }
+
+            if !self.wait_for_taken_job(taken_jobs, Some(deadline)) {
+                return Err(job);
+            }
+        }
+    }
+
+    /// Queues a job if the scheduler is not at capacity,
+    /// otherwise gives the job back.
+    pub fn try_push(&self, job: Job, options: &JobOptions) -> Result<(), Job> {
+        if !self.reserve() {
+            return Err(job);
+        }
+
+        let lane = options.priority.index();
+        self.queued_by_priority[lane].fetch_add(1, Ordering::SeqCst);
+
+        let job = QueuedJob {
+            id: self.next_job_id.fetch_add(1, Ordering::Relaxed),
+            job,
+            queued_at: Instant::now(),
+            token: options.token.clone(),
+        };
+
+        let own_queue = CURRENT_QUEUE.with(|current| {
+            current
+                .borrow()
+                .as_ref()
+                .filter(|(scheduler, _)| *scheduler == self.address())
+                .map(|(_, queue)| Arc::clone(queue))
+        });
+
+        if let Some(queue) = own_queue {
+            lock(&queue)[lane].push_back(job);
+        } else {
+            // the read lock is held while pushing so that a worker
+            // cannot unregister its queue in the meantime
+            let locals = self
+                .locals
+                .read()
+                .expect("locals lock should not be poisoned");
+
+            if locals.is_empty() {
+                lock(&self.global)[lane].push_back(job);
+            } else {
+                let index = self.next_local.fetch_add(1, Ordering::Relaxed) % locals.len();
+                lock(&locals[index])[lane].push_back(job);
+            }
+        }
+
+        self.wake_one();
+
+        Ok(())
+    }
+
+    fn reserve(&self) -> bool {
+        let Some(capacity) = self.capacity else {
+            self.queued.fetch_add(1, Ordering::SeqCst);
+            return true;
+        };
+
+        // sleeping workers take a job right away, so they
+        // count as extra room, like a rendezvous channel
+        self.queued
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |queued| {
+                (queued < capacity + self.sleeping.load(Ordering::SeqCst)).then_some(queued + 1)
+            })
+            .is_ok()
+    }
+
+    /// Blocks until a job is available for the worker owning `registration`,
+    /// for at most `idle_timeout`.
+    pub fn next_job(&self, registration: &Registration, idle_timeout: Duration) -> Next {
+        let mut idle_deadline = None;
+        let mut yields = 0;
+
+        loop {
+            if self.take_retirement() {
+                return Next::Retire;
+            }
+
+            if let Some(job) = self.find_job(&registration.queue) {
+                return Next::Job(job);
+            }
+
+            if self.is_closed() && self.queued.load(Ordering::SeqCst) == 0 {
+                return Next::Closed;
+            }
+
+            // giving the other threads a chance to push more jobs
+            // is much cheaper than going to sleep and being woken up
+            if yields < YIELDS_BEFORE_SLEEPING {
+                yields += 1;
+                thread::yield_now();
+            } else {
+                yields = 0;
+
+                let deadline = *idle_deadline.get_or_insert_with(|| Instant::now() + idle_timeout);
+
+                if !self.sleep(deadline) {
+                    return Next::Idle;
+                }
+            }
+        }
+    }
+
+    fn find_job(&self, own_queue: &Deque) -> Option<QueuedJob> {
+        Priority::ALL
+            .into_iter()
+            .filter(|priority| self.queued_by_priority[priority.index()].load(Ordering::SeqCst) > 0)
+            .find_map(|priority| self.find_job_with_priority(own_queue, priority))
+    }
+
+    fn find_job_with_priority(&self, own_queue: &Deque, priority: Priority) -> Option<QueuedJob> {
+        if let Some(job) = self.pop(own_queue, priority) {
+            return Some(job);
+        }
+
+        if let Some(job) = self.pop(&self.global, priority) {
+            return Some(job);
+        }
+
+        let locals = self
+            .locals
+            .read()
+            .expect("locals lock should not be poisoned");
+
+        if locals.is_empty() {
+            return None;
+        }
+
+        // start from a different queue every time so that
+        // the stealing is spread over all the workers
+        let start = self.next_local.fetch_add(1, Ordering::Relaxed);
+
+        (0..locals.len())
+            .map(|offset| &locals[(start + offset) % locals.len()])
+            .filter(|queue| !ptr::eq(queue.as_ref(), own_queue))
+            .find_map(|queue| self.pop(queue, priority))
+    }
+
+    fn pop(&self, queue: &Deque, priority: Priority) -> Option<QueuedJob> {
+        let mut queue = lock(queue);
+        let lane = priority.index();
+
+        loop {
+            let job = queue[lane].pop_front()?;
+            self.queued.fetch_sub(1, Ordering::SeqCst);
+            self.queued_by_priority[lane].fetch_sub(1, Ordering::SeqCst);
+
+            // the check is done while holding the lock so that
+            // discard_queued_jobs can account for every discarded job
+            if self.discard_jobs.load(Ordering::SeqCst) {
+                self.abandoned_jobs.fetch_add(1, Ordering::SeqCst);
+            } else if job.is_cancelled() {
+                self.cancelled_jobs.fetch_add(1, Ordering::SeqCst);
+                self.job_was_taken();
+            } else {
+                self.job_was_taken();
+                return Some(job);
+            }
+        }
+    }
+
+    // returns false if `deadline` was reached without being woken up
+    fn sleep(&self, deadline: Instant) -> bool {
+        let mut wakeups = lock(&self.sleep_lock);
+        self.sleeping.fetch_add(1, Ordering::SeqCst);
+
+        let mut timed_out = false;
+
+        // checked under the lock that pushes notify with, so no wakeup is lost
+        while *wakeups == 0
+            && self.queued.load(Ordering::SeqCst) == 0
+            && self.retiring.load(Ordering::SeqCst) == 0
+            && !self.is_closed()
+        {
+            let now = Instant::now();
+
+            if now >= deadline {
+                timed_out = true;
+                break;
+            }
+
+            wakeups = self
+                .job_available
+                .wait_timeout(wakeups, deadline - now)
+                .expect("sleep lock should not be poisoned")
+                .0;
+        }
+
+        // a wakeup means that whoever woke us already stopped counting us
+        if *wakeups > 0 {
+            *wakeups -= 1;
+        } else {
+            self.sleeping.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        !timed_out
+    }
+
+    fn wake_one(&self) {
+        if self.sleeping.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        let mut wakeups = lock(&self.sleep_lock);
+
+        // the woken worker stops counting as sleeping right away, so that
+        // the next pushes do not wake it again before it gets to run
+        if self.sleeping.load(Ordering::SeqCst) > 0 {
+            self.sleeping.fetch_sub(1, Ordering::SeqCst);
+            *wakeups += 1;
+            self.job_available.notify_one();
+        }
+    }
+
+    fn job_was_taken(&self) {
+        if self.capacity.is_some() {
+            *lock(&self.taken_jobs) += 1;
+            self.job_taken.notify_all();
+        }
+    }
+
+    fn taken_jobs(&self) -> usize {
+        *lock(&self.taken_jobs)
+    }
+
+    // returns true if a job was taken after `taken_jobs` jobs were taken
+    fn wait_for_taken_job(&self, taken_jobs: usize, deadline: Option<Instant>) -> bool {
+        let mut current_taken_jobs = lock(&self.taken_jobs);
+
+        while *current_taken_jobs == taken_jobs {
+            let Some(deadline) = deadline else {
+                current_taken_jobs = self
+                    .job_taken
+                    .wait(current_taken_jobs)
+                    .expect("taken_jobs lock should not be poisoned");
+
+                continue;
+            };
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return false;
+            }
+
+            current_taken_jobs = self
+                .job_taken
+                .wait_timeout(current_taken_jobs, deadline - now)
+                .expect("taken_jobs lock should not be poisoned")
+                .0;
+        }
+
+        true
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Asks `count` workers to exit. Idle workers exit right away,
+    /// busy workers exit after finishing their current job.
+    pub fn retire_workers(&self, count: usize) {
+        self.retiring.fetch_add(count, Ordering::SeqCst);
+
+        let _sleep_lock = lock(&self.sleep_lock);
+        self.job_available.notify_all();
+    }
+
+    fn take_retirement(&self) -> bool {
+        // checked first so that the common case needs no read-modify-write
+        self.retiring.load(Ordering::SeqCst) > 0
+            && self
+                .retiring
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |retiring| {
+                    retiring.checked_sub(1)
+                })
+                .is_ok()
+    }
+
+    /// Lets the workers exit once all the queued jobs are executed.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+
+        let _sleep_lock = lock(&self.sleep_lock);
+        self.job_available.notify_all();
+    }
+
+    /// Discards all the jobs that are still queued.
+    pub fn discard_queued_jobs(&self) {
+        self.discard_jobs.store(true, Ordering::SeqCst);
+
+        let locals = self
+            .locals
+            .read()
+            .expect("locals lock should not be poisoned");
+
+        for queue in locals.iter().map(AsRef::as_ref).chain([&self.global]) {
+            for priority in Priority::ALL {
+                // pop counts the discarded jobs
+                let _ = self.pop(queue, priority);
+            }
+        }
+
+        drop(locals);
+        self.close();
+    }
+
+    /// Returns the number of jobs that are waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    pub fn abandoned_jobs(&self) -> usize {
+        self.abandoned_jobs.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of jobs that were dropped from
+    /// the queue because their token was cancelled.
+    pub fn cancelled_jobs(&self) -> usize {
+        self.cancelled_jobs.load(Ordering::SeqCst)
+    }
+}
+
+/// The local queue of a worker of a [`Scheduler`].
+pub struct Registration<'a> {
+    scheduler: &'a Scheduler,
+    queue: Arc<Deque>,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        CURRENT_QUEUE.with(|current| current.borrow_mut().take());
+
+        self.scheduler
+            .locals
+            .write()
+            .expect("locals lock should not be poisoned")
+            .retain(|queue| !Arc::ptr_eq(queue, &self.queue));
+
+        // nobody can push to the queue anymore, so its jobs
+        // are moved where the other workers can find them
+        let leftover_jobs = mem::take(&mut *lock(&self.queue));
+
+        if leftover_jobs.iter().any(|lane| !lane.is_empty()) {
+            let mut global = lock(&self.scheduler.global);
+
+            for (global_lane, leftover_lane) in global.iter_mut().zip(leftover_jobs) {
+                global_lane.extend(leftover_lane);
+            }
+
+            drop(global);
+
+            let _sleep_lock = lock(&self.scheduler.sleep_lock);
+            self.scheduler.job_available.notify_all();
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().expect("scheduler lock should not be poisoned")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+    fn counting_job(counter: &Arc<AtomicUsize>) -> Job {
+        let counter = Arc::clone(counter);
+
+        Box::new(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+    }
+
+    // runs `f` while another thread has a registered local queue
+    fn with_other_worker(scheduler: &Scheduler, f: impl FnOnce()) {
+        thread::scope(|s| {
+            let (registered_sender, registered_receiver) = mpsc::channel();
+            let (release_sender, release_receiver) = mpsc::channel::<()>();
+
+            // a worker that never takes its own jobs
+            s.spawn(move || {
+                let _registration = scheduler.register();
+                registered_sender.send(()).unwrap();
+                let _ = release_receiver.recv();
+            });
+
+            registered_receiver.recv().unwrap();
+            f();
+            drop(release_sender);
+        });
+    }
+
+    #[test]
+    fn pushes_from_a_worker_go_to_its_local_queue() {
+        let scheduler = Scheduler::new(None);
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        with_other_worker(&scheduler, || {
+            let registration = scheduler.register();
+
+            for _ in 0..3 {
+                assert!(scheduler
+                    .try_push(counting_job(&counter), &JobOptions::default())
+                    .is_ok());
+            }
+
+            assert_eq!(
+                lock(&registration.queue).len(),
+                3,
+                "the jobs did not go to the local queue"
+            );
+        });
+    }
+
+    #[test]
+    fn steals_jobs_from_other_workers() {
+        let scheduler = Scheduler::new(None);
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        with_other_worker(&scheduler, || {
+            // not registered yet, so the jobs go to the other worker
+            for _ in 0..3 {
+                assert!(scheduler
+                    .try_push(counting_job(&counter), &JobOptions::default())
+                    .is_ok());
+            }
+
+            let registration = scheduler.register();
+
+            for _ in 0..3 {
+                let stolen_job = scheduler
+                    .find_job(&registration.queue)
+                    .expect("no job was stolen");
+
+                (stolen_job.job)();
+            }
+        });
+
+        assert_eq!(
+            counter.load(Ordering::SeqCst),
+            3,
+            "not all jobs were executed"
+        );
+    }
+
+    #[test]
+    fn try_push_fails_when_at_capacity() {
+        let scheduler = Scheduler::new(Some(2));
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        assert!(scheduler
+            .try_push(counting_job(&counter), &JobOptions::default())
+            .is_ok());
+        assert!(scheduler
+            .try_push(counting_job(&counter), &JobOptions::default())
+            .is_ok());
+        assert!(
+            scheduler
+                .try_push(counting_job(&counter), &JobOptions::default())
+                .is_err(),
+            "the capacity was not respected"
+        );
+        assert!(
+            scheduler
+                .push_until(
+                    counting_job(&counter),
+                    &JobOptions::default(),
+                    Instant::now() + Duration::from_millis(10)
+                )
+                .is_err(),
+            "the capacity was not respected"
+        );
+    }
+
+    #[test]
+    fn next_job_returns_closed_after_close() {
+        let scheduler = Scheduler::new(None);
+        let registration = scheduler.register();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        scheduler.push(counting_job(&counter), &JobOptions::default());
+        scheduler.close();
+
+        assert!(
+            matches!(
+                scheduler.next_job(&registration, IDLE_TIMEOUT),
+                Next::Job(_)
+            ),
+            "the queued job was not returned"
+        );
+        assert!(
+            matches!(
+                scheduler.next_job(&registration, IDLE_TIMEOUT),
+                Next::Closed
+            ),
+            "a job was returned after close"
+        );
+    }
+
+    #[test]
+    fn discard_queued_jobs_counts_abandoned_jobs() {
+        let scheduler = Scheduler::new(None);
+        let registration = scheduler.register();
+        let counter = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..4 {
+            scheduler.push(counting_job(&counter), &JobOptions::default());
+        }
+
+        scheduler.discard_queued_jobs();
+
+        assert_eq!(scheduler.abandoned_jobs(), 4, "not all jobs were abandoned");
+        assert!(
+            matches!(
+                scheduler.next_job(&registration, IDLE_TIMEOUT),
+                Next::Closed
+            ),
+            "a discarded job was returned"
+        );
+    }
+
+    #[test]
+    fn takes_jobs_with_a_higher_priority_first() {
+        let scheduler = Scheduler::new(None);
+        let registration = scheduler.register();
+        let (order_sender, order_receiver) = mpsc::channel();
+
+        for priority in [Priority::Low, Priority::Normal, Priority::High] {
+            let order_sender = order_sender.clone();
+            let options = JobOptions {
+                priority,
+                token: None,
+            };
+
+            scheduler.push(
+                Box::new(move || order_sender.send(priority).unwrap()),
+                &options,
+            );
+        }
+
+        for _ in 0..3 {
+            let Next::Job(job) = scheduler.next_job(&registration, IDLE_TIMEOUT) else {
+                panic!("no job was returned");
+            };
+
+            (job.job)();
+        }
+
+        assert_eq!(
+            order_receiver.try_iter().collect::<Vec<_>>(),
+            [Priority::High, Priority::Normal, Priority::Low],
+            "the jobs were not taken by priority"
+        );
+    }
+
+    #[test]
+    fn skips_cancelled_jobs() {
+        let scheduler = Scheduler::new(None);
+        let registration = scheduler.register();
+        let counter = Arc::new(AtomicUsize::new(0));
+        let token = CancellationToken::new();
+
+        let options = JobOptions {
+            priority: Priority::Normal,
+            token: Some(token.clone()),
+        };
+
+        scheduler.push(counting_job(&counter), &options);
+        token.cancel();
+        scheduler.close();
+
+        assert!(
+            matches!(
+                scheduler.next_job(&registration, IDLE_TIMEOUT),
+                Next::Closed
+            ),
+            "a cancelled job was returned"
+        );
+        assert_eq!(scheduler.cancelled_jobs(), 1, "the job was not cancelled");
+    }
+
+    #[test]
+    fn next_job_returns_idle_after_the_idle_timeout() {
+        let scheduler = Scheduler::new(None);
+        let registration = scheduler.register();
+
+        assert!(
+            matches!(
+                scheduler.next_job(&registration, Duration::from_millis(10)),
+                Next::Idle
+            ),
+            "the idle timeout was not respected"
+        );
+    }
+
+    #[test]
+    fn retire_workers_wakes_up_sleeping_workers() {
+        let scheduler = Scheduler::new(None);
+
+        thread::scope(|s| {
+            let worker = s.spawn(|| {
+                let registration = scheduler.register();
+                matches!(
+                    scheduler.next_job(&registration, IDLE_TIMEOUT),
+                    Next::Retire
+                )
+            });
+
+            thread::sleep(Duration::from_millis(10));
+            scheduler.retire_workers(1);
+
+            assert!(worker.join().unwrap(), "the worker was not retired");
+        });
+
+        assert_eq!(
+            scheduler.retiring.load(Ordering::SeqCst),
+            0,
+            "the retirement was not taken"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use crate::ThreadPool;
+
+use std::{
+    any::Any,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+/// Executes jobs that can borrow data living outside of the scope,
+/// see [`ThreadPool::scope`].
+pub struct Scope<'scope, 'env: 'scope> {
+    pool: &'scope ThreadPool,
+    state: Arc<ScopeState>,
+
+    // invariant lifetimes, like in std::thread::Scope
+    lifetimes: PhantomData<(&'scope mut &'scope (), &'env mut &'env ())>,
+}
+
+#[derive(Default)]
+struct ScopeState {
+    pending_jobs: Mutex<usize>,
+    all_jobs_done: Condvar,
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl<'scope> Scope<'scope, '_> {
+    /// Runs a given piece of code on a thread from the [`ThreadPool`] of
+    /// the scope. The piece of code can borrow data living outside of
+    /// the scope and can use the scope to execute more pieces of code.
+    ///
+    /// # Panics
+    ///
+    /// If the piece of code panics, the panic is caught and
+    /// [`ThreadPool::scope`] panics once all the pieces of code are done.
+    pub fn execute<F>(&'scope self, f: F)
+    where
+        F: FnOnce() + Send + 'scope,
+    {
+        *lock(&self.state.pending_jobs) += 1;
+
+        let scope_job = ScopeJob {
+            f,
+            on_done: JobDone(Arc::clone(&self.state)),
+        };
+
+        let job: Box<dyn FnOnce() + Send + 'scope> = Box::new(move || scope_job.run());
+
+        // SAFETY: only the lifetime changes. The job cannot outlive 'scope
+        // because ThreadPool::scope waits for all the jobs of the scope to
+        // be done, either by running or by being dropped, before returning.
+        let job: Box<dyn FnOnce() + Send + 'static> = unsafe { mem::transmute(job) };
+
+        self.pool.execute(job);
+    }
+}
+
+// the fields are dropped in order, so `f` is always
+// dropped before the scope learns that the job is done
+struct ScopeJob<F> {
+    f: F,
+    on_done: JobDone,
+}
+
+impl<F: FnOnce()> ScopeJob<F> {
+    fn run(self) {
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(self.f)) {
+            lock(&self.on_done.0.panic).get_or_insert(panic);
+        }
+    }
+}
+
+// dropped even if the pool drops the job without running
+// it, so the scope never waits for such a job
+struct JobDone(Arc<ScopeState>);
+
+impl Drop for JobDone {
+    fn drop(&mut self) {
+        let mut pending_jobs = lock(&self.0.pending_jobs);
+        *pending_jobs -= 1;
+
+        if *pending_jobs == 0 {
+            self.0.all_jobs_done.notify_all();
+        }
+    }
+}
+
+/// See [`ThreadPool::scope`].
+pub fn scope<'env, F, T>(pool: &ThreadPool, f: F) -> T
+where
+    F: for<'scope> FnOnce(&'scope Scope<'scope, 'env>) -> T,
+{
+    let scope = Scope {
+        pool,
+        state: Arc::default(),
+        lifetimes: PhantomData,
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+
+    let mut pending_jobs = lock(&scope.state.pending_jobs);
+
+    while *pending_jobs > 0 {
+        pending_jobs = scope
+            .state
+            .all_jobs_done
+            .wait(pending_jobs)
+            .expect("pending_jobs lock should not be poisoned");
+    }
+
+    drop(pending_jobs);
+
+    match result {
+        Err(panic) => panic::resume_unwind(panic),
+        Ok(result) => {
+            if let Some(panic) = lock(&scope.state.panic).take() {
+                panic::resume_unwind(panic);
+            }
+
+            result
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().expect("scope lock should not be poisoned")
+}
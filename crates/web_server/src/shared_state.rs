@@ -0,0 +1,267 @@
+use crate::{
+    logger::{PoolEvent, SharedLogger},
+    scheduler::Scheduler,
+    stats::{AtomicHistogram, PoolStats},
+    thread_settings::ThreadSettings,
+    ThreadPoolBuilder,
+};
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Why a worker thread exited, as reported to the supervisor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkerExit {
+    Died(usize),
+    Retired(usize),
+}
+
+#[derive(Debug)]
+pub struct SharedState {
+    scheduler: Scheduler,
+
+    // the number of workers that are running or being spawned,
+    // without the ones that were asked to retire
+    size: AtomicUsize,
+    min_size: AtomicUsize,
+    max_size: AtomicUsize,
+    idle_timeout: Duration,
+    logger: SharedLogger,
+    thread_settings: ThreadSettings,
+
+    completed_jobs: AtomicUsize,
+    panicked_jobs: AtomicUsize,
+    running_jobs: AtomicUsize,
+    queue_wait: AtomicHistogram,
+    run_time: AtomicHistogram,
+    live_workers: Mutex<usize>,
+    worker_exited: Condvar,
+    next_worker_id: AtomicUsize,
+    restarted_workers: AtomicUsize,
+    worker_exits: Mutex<Option<Sender<WorkerExit>>>,
+}
+
+impl SharedState {
+    pub fn new(builder: &ThreadPoolBuilder) -> SharedState {
+        SharedState {
+            scheduler: Scheduler::new(builder.queue_capacity),
+            size: AtomicUsize::new(0),
+            min_size: AtomicUsize::new(0),
+            max_size: AtomicUsize::new(0),
+            idle_timeout: builder.idle_timeout,
+            logger: builder.logger.clone(),
+            thread_settings: builder.thread_settings.clone(),
+            completed_jobs: AtomicUsize::new(0),
+            panicked_jobs: AtomicUsize::new(0),
+            running_jobs: AtomicUsize::new(0),
+            queue_wait: AtomicHistogram::default(),
+            run_time: AtomicHistogram::default(),
+            live_workers: Mutex::new(0),
+            worker_exited: Condvar::new(),
+            next_worker_id: AtomicUsize::new(0),
+            restarted_workers: AtomicUsize::new(0),
+            worker_exits: Mutex::new(None),
+        }
+    }
+
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+
+    pub fn log(&self, event: &PoolEvent) {
+        self.logger.log(event);
+    }
+
+    pub fn thread_settings(&self) -> &ThreadSettings {
+        &self.thread_settings
+    }
+
+    pub fn size(&self) -> usize {
+        self.size.load(Ordering::SeqCst)
+    }
+
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Sets the limits within which the pool grows and shrinks,
+    /// and the size of the pool to `min_size`.
+    ///
+    /// Returns the previous size of the pool.
+    pub fn resize(&self, min_size: usize, max_size: usize) -> usize {
+        self.min_size.store(min_size, Ordering::SeqCst);
+        self.max_size.store(max_size, Ordering::SeqCst);
+        self.size.swap(min_size, Ordering::SeqCst)
+    }
+
+    /// Returns `true` if the pool may get another worker, in
+    /// which case the caller is responsible for spawning it.
+    pub fn try_grow(&self) -> bool {
+        let max_size = self.max_size.load(Ordering::SeqCst);
+
+        self.size
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |size| {
+                (size < max_size).then_some(size + 1)
+            })
+            .is_ok()
+    }
+
+    /// Returns `true` if an idle worker may exit, in
+    /// which case the caller is responsible for exiting.
+    pub fn try_shrink(&self) -> bool {
+        let min_size = self.min_size.load(Ordering::SeqCst);
+
+        self.size
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |size| {
+                (size > min_size).then_some(size - 1)
+            })
+            .is_ok()
+    }
+
+    /// Returns `true` if every worker is busy or about to be.
+    pub fn is_busy(&self) -> bool {
+        self.running_jobs() + self.scheduler.queued() >= self.size()
+    }
+
+    pub fn job_started(&self, queue_wait: Duration) {
+        self.running_jobs.fetch_add(1, Ordering::SeqCst);
+        self.queue_wait.record(queue_wait);
+    }
+
+    pub fn job_finished(&self, run_time: Duration) {
+        self.running_jobs.fetch_sub(1, Ordering::SeqCst);
+        self.completed_jobs.fetch_add(1, Ordering::SeqCst);
+        self.run_time.record(run_time);
+    }
+
+    pub fn job_panicked(&self, run_time: Duration) {
+        self.running_jobs.fetch_sub(1, Ordering::SeqCst);
+        self.panicked_jobs.fetch_add(1, Ordering::SeqCst);
+        self.run_time.record(run_time);
+    }
+
+    pub fn completed_jobs(&self) -> usize {
+        self.completed_jobs.load(Ordering::SeqCst)
+    }
+
+    pub fn running_jobs(&self) -> usize {
+        self.running_jobs.load(Ordering::SeqCst)
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let busy_workers = self.running_jobs();
+
+        PoolStats {
+            queued_jobs: self.scheduler.queued(),
+            busy_workers,
+            // retiring workers may still be busy for a while
+            idle_workers: self.size().saturating_sub(busy_workers),
+            completed_jobs: self.completed_jobs(),
+            panicked_jobs: self.panicked_jobs.load(Ordering::SeqCst),
+            cancelled_jobs: self.scheduler.cancelled_jobs(),
+            queue_wait: self.queue_wait.snapshot(),
+            run_time: self.run_time.snapshot(),
+        }
+    }
+
+    pub fn worker_started(&self) {
+        *self
+            .live_workers
+            .lock()
+            .expect("live_workers lock should not be poisoned") += 1;
+    }
+
+    pub fn worker_exited(&self) {
+        *self
+            .live_workers
+            .lock()
+            .expect("live_workers lock should not be poisoned") -= 1;
+
+        self.worker_exited.notify_all();
+    }
+
+    /// Waits until all the workers have exited or until `deadline` is reached.
+    /// Without a deadline, waits for as long as it takes.
+    ///
+    /// Returns `true` if all the workers have exited.
+    pub fn wait_for_workers(&self, deadline: Option<Instant>) -> bool {
+        let mut live_workers = self
+            .live_workers
+            .lock()
+            .expect("live_workers lock should not be poisoned");
+
+        while *live_workers > 0 {
+            let Some(deadline) = deadline else {
+                live_workers = self
+                    .worker_exited
+                    .wait(live_workers)
+                    .expect("live_workers lock should not be poisoned");
+
+                continue;
+            };
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return false;
+            }
+
+            live_workers = self
+                .worker_exited
+                .wait_timeout(live_workers, deadline - now)
+                .expect("live_workers lock should not be poisoned")
+                .0;
+        }
+
+        true
+    }
+
+    pub fn next_worker_id(&self) -> usize {
+        self.next_worker_id.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub fn worker_restarted(&self) {
+        self.restarted_workers.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn restarted_workers(&self) -> usize {
+        self.restarted_workers.load(Ordering::SeqCst)
+    }
+
+    /// Starts sending the workers whose threads died because of
+    /// a panic or retired to the returned [`Receiver`].
+    pub fn supervise(&self) -> Receiver<WorkerExit> {
+        let (sender, receiver) = mpsc::channel();
+
+        *self
+            .worker_exits
+            .lock()
+            .expect("worker_exits lock should not be poisoned") = Some(sender);
+
+        receiver
+    }
+
+    pub fn stop_supervising(&self) {
+        self.worker_exits
+            .lock()
+            .expect("worker_exits lock should not be poisoned")
+            .take();
+    }
+
+    /// Returns `true` if someone is supervising the workers and received
+    /// the exit. In that case, for a [`WorkerExit::Died`], the supervisor
+    /// is responsible for calling [`SharedState::worker_exited`].
+    pub fn report_worker_exit(&self, exit: WorkerExit) -> bool {
+        self.worker_exits
+            .lock()
+            .expect("worker_exits lock should not be poisoned")
+            .as_ref()
+            .is_some_and(|sender| sender.send(exit).is_ok())
+    }
+}
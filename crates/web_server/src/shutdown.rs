@@ -0,0 +1,17 @@
+/// Describes what happened to the jobs of a [`ThreadPool`](crate::ThreadPool)
+/// that was shut down with [`ThreadPool::shutdown`](crate::ThreadPool::shutdown)
+/// or [`ThreadPool::shutdown_now`](crate::ThreadPool::shutdown_now).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// The number of jobs that ran to completion during
+    /// the lifetime of the pool.
+    pub completed: usize,
+
+    /// The number of jobs that were discarded from the queue
+    /// without ever being executed.
+    pub abandoned: usize,
+
+    /// The number of jobs that were still executing when the
+    /// shutdown returned. Their threads are left to finish on their own.
+    pub unfinished: usize,
+}
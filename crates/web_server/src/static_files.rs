@@ -0,0 +1,508 @@
+mod cache;
+mod ranges;
+
+pub use cache::FileCache;
+
+use ranges::Ranges;
+
+use crate::http::{content_type, format_date, parse_date, Body, Headers, Response, Status};
+
+use std::{
+    fs::{self, File},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Serves the files under a root directory.
+#[derive(Clone, Debug)]
+pub struct StaticFiles {
+    // canonical, so that resolved paths can be compared with it
+    root: PathBuf,
+
+    cache: Option<Arc<FileCache>>,
+}
+
+impl StaticFiles {
+    /// Creates a [`StaticFiles`] serving the files under `root`,
+    /// keeping their contents in `cache` if there is one.
+    pub fn new(root: impl AsRef<Path>, cache: Option<Arc<FileCache>>) -> io::Result<StaticFiles> {
+        Ok(StaticFiles {
+            root: fs::canonicalize(root)?,
+            cache,
+        })
+    }
+
+    /// Answers with the file at `path`, which is relative to the root
+    /// and already percent-decoded. Directories are answered with their
+    /// `index.html`. Returns the status to answer with if there is no
+    /// file to serve.
+    ///
+    /// The response has an `ETag` and a `Last-Modified`, and is a 304
+    /// without a body if the conditional fields of `headers`, the ones of
+    /// the request, show that the client has the file already. It is a 206
+    /// with the ranges of the file if `headers` has a `Range`.
+    ///
+    /// The file is streamed, so it is not read into memory,
+    /// unless it is kept in the cache.
+    pub fn serve(&self, path: &str, headers: &Headers) -> Result<Response, Status> {
+        let mut file_path = self.root.clone();
+
+        for component in path.split('/') {
+            match component {
+                "" | "." => {}
+                ".." => return Err(Status::Forbidden),
+                // would be a separator or a drive on some platforms
+                _ if component.contains(['\\', ':', '\0']) => return Err(Status::Forbidden),
+                _ => file_path.push(component),
+            }
+        }
+
+        let mut file_path = self.resolve(&file_path)?;
+
+        if file_path.is_dir() {
+            file_path = self.resolve(&file_path.join("index.html"))?;
+        }
+
+        let file = File::open(&file_path).map_err(|e| error_status(&e))?;
+        let metadata = file.metadata().map_err(|e| error_status(&e))?;
+
+        if !metadata.is_file() {
+            return Err(Status::NotFound);
+        }
+
+        let length = metadata.len();
+
+        // some platforms do not record when files are modified
+        let modified = metadata.modified().ok();
+        let etag = modified.map(|modified| entity_tag(modified, length));
+        let content_type = content_type(&file_path);
+
+        let mut response = match (modified, &etag) {
+            (Some(modified), Some(etag)) if is_not_modified(headers, etag, modified) => {
+                // not sent, but the fields depending on it can be added like for a 200
+                let mut response = Response::new(Status::NotModified, Vec::new());
+                response.headers.add("Content-Type", content_type);
+                response
+            }
+            _ => {
+                let body = match (&self.cache, modified) {
+                    (Some(cache), Some(modified)) => cache
+                        .body(&file_path, modified, length, file)
+                        .map_err(|_| Status::InternalServerError)?,
+                    _ => Body::File { file, length },
+                };
+
+                let ranges = headers
+                    .get("Range")
+                    .filter(|_| is_range_current(headers, etag.as_deref(), modified))
+                    .and_then(|field| ranges::parse(field, length));
+
+                let mut response = match ranges {
+                    None => {
+                        let mut response = Response::new(Status::Ok, body);
+                        response.headers.add("Content-Type", content_type);
+                        response
+                    }
+                    Some(Ranges::Satisfiable(ranges)) => {
+                        ranges::partial_response(body, &ranges, content_type, length)
+                            .map_err(|_| Status::InternalServerError)?
+                    }
+                    Some(Ranges::Unsatisfiable) => {
+                        let mut response = Response::new(Status::RangeNotSatisfiable, Vec::new());
+                        response
+                            .headers
+                            .add("Content-Range", format!("bytes */{length}"));
+
+                        response
+                    }
+                };
+
+                response.headers.add("Accept-Ranges", "bytes");
+                response
+            }
+        };
+
+        if let (Some(modified), Some(etag)) = (modified, etag) {
+            response.headers.add("ETag", etag);
+            response.headers.add("Last-Modified", format_date(modified));
+        }
+
+        Ok(response)
+    }
+
+    // follows the symlinks of `path` to catch the ones leading out of the root
+    fn resolve(&self, path: &Path) -> Result<PathBuf, Status> {
+        let path = fs::canonicalize(path).map_err(|e| error_status(&e))?;
+
+        if path.starts_with(&self.root) {
+            Ok(path)
+        } else {
+            Err(Status::Forbidden)
+        }
+    }
+}
+
+// changes whenever the file is modified, as long as the file system records it precisely
+fn entity_tag(modified: SystemTime, length: u64) -> String {
+    let modified = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    format!(
+        "\"{length:x}-{:x}-{:x}\"",
+        modified.as_secs(),
+        modified.subsec_nanos()
+    )
+}
+
+// `If-None-Match` takes precedence, since entity tags are more precise than dates
+fn is_not_modified(headers: &Headers, etag: &str, modified: SystemTime) -> bool {
+    if headers.contains("If-None-Match") {
+        return headers
+            .get_all("If-None-Match")
+            .flat_map(|tags| tags.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || weakly_equal(tag, etag));
+    }
+
+    headers
+        .get("If-Modified-Since")
+        .and_then(parse_date)
+        .is_some_and(|since| seconds(modified) <= seconds(since))
+}
+
+// the ranges of a file that changed since the client got its other parts
+// would not fit with them, so `If-Range` makes the whole file be sent instead
+fn is_range_current(headers: &Headers, etag: Option<&str>, modified: Option<SystemTime>) -> bool {
+    match headers.get("If-Range") {
+        None => true,
+        // weak tags cannot be used, since the bytes could differ
+        Some(tag) if tag.starts_with('"') => etag == Some(tag),
+        Some(tag) if tag.starts_with("W/") => false,
+        Some(date) => parse_date(date)
+            .zip(modified)
+            .is_some_and(|(date, modified)| seconds(date) == seconds(modified)),
+    }
+}
+
+// compressed responses have weak tags, which still match the strong ones
+fn weakly_equal(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+// the dates are to the second, unlike the modification times
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// a path going through a file is not found as well, whatever the platform calls it
+fn error_status(e: &io::Error) -> Status {
+    if e.kind() == ErrorKind::PermissionDenied {
+        Status::Forbidden
+    } else {
+        Status::NotFound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, io::Read, process, time::Duration};
+
+    // a fresh directory for every test, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = env::temp_dir().join(format!("web-server-{}-{name}", process::id()));
+            let _ = fs::remove_dir_all(&path);
+
+            fs::create_dir_all(path.join("root/docs")).unwrap();
+            fs::write(path.join("root/index.html"), "<h1>Home</h1>").unwrap();
+            fs::write(path.join("root/docs/logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+            fs::write(path.join("secret.txt"), "secret").unwrap();
+
+            TempDir(path)
+        }
+
+        fn static_files(&self) -> StaticFiles {
+            StaticFiles::new(self.0.join("root"), None).unwrap()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn contents(response: Response) -> Vec<u8> {
+        match response.body {
+            Body::Bytes(bytes) => bytes,
+            Body::Shared(bytes) => bytes.to_vec(),
+            Body::File { mut file, .. } => {
+                let mut contents = Vec::new();
+                file.read_to_end(&mut contents).unwrap();
+                contents
+            }
+            Body::FileRanges { .. } => panic!("the whole file was not served"),
+        }
+    }
+
+    fn headers(name: &str, value: &str) -> Headers {
+        let mut headers = Headers::default();
+        headers.add(name, value);
+        headers
+    }
+
+    #[test]
+    fn serves_files_with_their_content_type() {
+        let dir = TempDir::new("serves");
+        let static_files = dir.static_files();
+
+        let response = static_files
+            .serve("docs/logo.png", &Headers::default())
+            .unwrap();
+
+        assert_eq!(
+            response.headers.get("Content-Type"),
+            Some("image/png"),
+            "the content type is different"
+        );
+        assert_eq!(response.body.length(), 4, "the length is different");
+        assert_eq!(
+            contents(response),
+            [0x89, b'P', b'N', b'G'],
+            "the contents are different"
+        );
+
+        assert_eq!(
+            contents(static_files.serve("", &Headers::default()).unwrap()),
+            b"<h1>Home</h1>",
+            "the index of the root was not served"
+        );
+        assert_eq!(
+            static_files
+                .serve("docs/missing.png", &Headers::default())
+                .unwrap_err(),
+            Status::NotFound,
+            "a missing file was not reported"
+        );
+        assert_eq!(
+            static_files.serve("docs", &Headers::default()).unwrap_err(),
+            Status::NotFound,
+            "a directory without an index was not reported"
+        );
+    }
+
+    #[test]
+    fn rejects_paths_leading_out_of_the_root() {
+        let dir = TempDir::new("traversal");
+        let static_files = dir.static_files();
+
+        for path in ["../secret.txt", "docs/../../secret.txt", "..\\secret.txt"] {
+            assert_eq!(
+                static_files.serve(path, &Headers::default()).unwrap_err(),
+                Status::Forbidden,
+                "{path} was not rejected"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_leading_out_of_the_root() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new("symlinks");
+        symlink(dir.0.join("secret.txt"), dir.0.join("root/secret.txt")).unwrap();
+        symlink(dir.0.join("root/index.html"), dir.0.join("root/home.html")).unwrap();
+
+        let static_files = dir.static_files();
+
+        assert_eq!(
+            static_files
+                .serve("secret.txt", &Headers::default())
+                .unwrap_err(),
+            Status::Forbidden,
+            "a symlink leading out of the root was followed"
+        );
+        assert!(
+            static_files.serve("home.html", &Headers::default()).is_ok(),
+            "a symlink staying in the root was not followed"
+        );
+    }
+
+    #[test]
+    fn answers_with_304_when_the_client_has_the_file() {
+        let dir = TempDir::new("conditional");
+        let static_files = dir.static_files();
+
+        let response = static_files
+            .serve("index.html", &Headers::default())
+            .unwrap();
+        let etag = response.headers.get("ETag").unwrap().to_string();
+        let last_modified = response.headers.get("Last-Modified").unwrap().to_string();
+
+        let cases = [
+            (headers("If-None-Match", &etag), Status::NotModified),
+            (
+                headers("If-None-Match", &format!("\"other\", W/{etag}")),
+                Status::NotModified,
+            ),
+            (headers("If-None-Match", "\"other\""), Status::Ok),
+            (
+                headers("If-Modified-Since", &last_modified),
+                Status::NotModified,
+            ),
+            (
+                headers("If-Modified-Since", "Thu, 01 Jan 1970 00:00:00 GMT"),
+                Status::Ok,
+            ),
+            (headers("If-Modified-Since", "yesterday"), Status::Ok),
+        ];
+
+        for (headers, status) in cases {
+            let response = static_files.serve("index.html", &headers).unwrap();
+
+            assert_eq!(
+                response.status, status,
+                "{headers:?} was not evaluated correctly"
+            );
+            assert_eq!(
+                response.headers.get("ETag"),
+                Some(etag.as_str()),
+                "the entity tag is missing for {headers:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn caches_files_until_they_are_modified() {
+        let dir = TempDir::new("cache");
+        let cache = Arc::new(FileCache::new(1024));
+        let static_files = StaticFiles::new(dir.0.join("root"), Some(cache)).unwrap();
+
+        assert_eq!(
+            contents(
+                static_files
+                    .serve("index.html", &Headers::default())
+                    .unwrap()
+            ),
+            b"<h1>Home</h1>",
+            "the file was not served"
+        );
+
+        // the cache is keyed by modification time, so only a file that
+        // looks unmodified can reveal that it was served from memory
+        let path = dir.0.join("root/index.html");
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, "<h1>Away</h1>").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        assert_eq!(
+            contents(
+                static_files
+                    .serve("index.html", &Headers::default())
+                    .unwrap()
+            ),
+            b"<h1>Home</h1>",
+            "the file was not served from the cache"
+        );
+
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified + Duration::from_secs(1))
+            .unwrap();
+
+        assert_eq!(
+            contents(
+                static_files
+                    .serve("index.html", &Headers::default())
+                    .unwrap()
+            ),
+            b"<h1>Away</h1>",
+            "the modified file was not read again"
+        );
+    }
+
+    #[test]
+    fn answers_ranges_of_the_file() {
+        let dir = TempDir::new("ranges");
+        fs::write(dir.0.join("root/digits.txt"), "0123456789").unwrap();
+        let static_files = dir.static_files();
+
+        let written = |range: &str| {
+            let response = static_files
+                .serve("digits.txt", &headers("Range", range))
+                .unwrap();
+
+            let mut output = Vec::new();
+            response.write_to(&mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let response = written("bytes=-3");
+
+        assert!(
+            response.starts_with("HTTP/1.1 206 PARTIAL CONTENT\r\n"),
+            "the range was not answered"
+        );
+        assert!(
+            response.contains("Content-Range: bytes 7-9/10\r\n"),
+            "the range is not described"
+        );
+        assert!(response.ends_with("\r\n\r\n789"), "the range was not sent");
+
+        let response = written("bytes=1-2,5-");
+
+        assert!(
+            response.contains("Content-Type: multipart/byteranges; boundary="),
+            "the ranges are not in a multipart body"
+        );
+        assert!(
+            response.contains("Content-Range: bytes 1-2/10\r\n\r\n12\r\n--"),
+            "the first range was not sent"
+        );
+        assert!(
+            response.contains("Content-Range: bytes 5-9/10\r\n\r\n56789\r\n--"),
+            "the second range was not sent"
+        );
+
+        let response = written("bytes=10-");
+
+        assert!(
+            response.starts_with("HTTP/1.1 416 RANGE NOT SATISFIABLE\r\n"),
+            "the unsatisfiable range was not rejected"
+        );
+        assert!(
+            response.contains("Content-Range: bytes */10\r\n"),
+            "the length of the file is not given"
+        );
+
+        let mut headers = headers("Range", "bytes=0-1");
+        headers.add("If-Range", "\"outdated\"");
+        let response = static_files.serve("digits.txt", &headers).unwrap();
+
+        assert_eq!(
+            response.status,
+            Status::Ok,
+            "the range of a modified file was answered"
+        );
+        assert_eq!(
+            response.headers.get("Accept-Ranges"),
+            Some("bytes"),
+            "the ranges are not advertised"
+        );
+    }
+}
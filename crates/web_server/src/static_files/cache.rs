@@ -0,0 +1,169 @@
+use crate::http::Body;
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+/// Keeps the contents of the served files in memory until they are
+/// modified, so that they are not read from the disk on every request.
+///
+/// Files stop being cached once the cache holds `capacity` bytes, so
+/// nothing is evicted to make room for them. The files that do not fit
+/// are streamed instead of being read into memory.
+#[derive(Debug)]
+pub struct FileCache {
+    capacity: u64,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    files: HashMap<PathBuf, CachedFile>,
+
+    // the sum of the lengths of the files
+    size: u64,
+}
+
+#[derive(Debug)]
+struct CachedFile {
+    // the file is read again when they change
+    modified: SystemTime,
+    length: u64,
+
+    contents: Arc<[u8]>,
+}
+
+impl FileCache {
+    /// Creates an empty [`FileCache`] holding up to `capacity` bytes.
+    pub fn new(capacity: u64) -> FileCache {
+        FileCache {
+            capacity,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Returns the body sending the file at `path`, which was modified at
+    /// `modified` and is `length` bytes long. The file is read from `file`
+    /// only if it is not cached yet, and is streamed from it if it does
+    /// not fit in the cache.
+    pub fn body(
+        &self,
+        path: &Path,
+        modified: SystemTime,
+        length: u64,
+        file: File,
+    ) -> io::Result<Body> {
+        {
+            let mut state = self.lock();
+
+            if let Some(cached) = state.files.get(path) {
+                if cached.modified == modified && cached.length == length {
+                    return Ok(Body::Shared(Arc::clone(&cached.contents)));
+                }
+            }
+
+            if let Some(outdated) = state.files.remove(path) {
+                state.size -= outdated.length;
+            }
+
+            if state.size + length > self.capacity {
+                return Ok(Body::File { file, length });
+            }
+        }
+
+        // read without holding the lock, so that other files can be served meanwhile
+        let mut contents = Vec::new();
+        file.take(length).read_to_end(&mut contents)?;
+
+        // the length was already checked, so a file that shrank is not served
+        if (contents.len() as u64) < length {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let contents = Arc::<[u8]>::from(contents);
+        let mut state = self.lock();
+
+        // the file may have been cached by another request meanwhile
+        if let Some(outdated) = state.files.remove(path) {
+            state.size -= outdated.length;
+        }
+
+        // other files may have filled the cache meanwhile
+        if state.size + length <= self.capacity {
+            state.size += length;
+            state.files.insert(
+                path.to_path_buf(),
+                CachedFile {
+                    modified,
+                    length,
+                    contents: Arc::clone(&contents),
+                },
+            );
+        }
+
+        Ok(Body::Shared(contents))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("file cache lock should not be poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{env, fs, process};
+
+    #[test]
+    fn streams_the_files_that_do_not_fit() {
+        let path = env::temp_dir().join(format!("web-server-{}-cache.txt", process::id()));
+        fs::write(&path, "0123456789").unwrap();
+
+        let cache = FileCache::new(8);
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let body = cache.body(&path, modified, 10, File::open(&path).unwrap());
+
+        fs::remove_file(&path).unwrap();
+
+        assert!(
+            matches!(body.unwrap(), Body::File { length: 10, .. }),
+            "a file larger than the cache was read into memory"
+        );
+        assert_eq!(
+            cache.lock().size,
+            0,
+            "a file larger than the cache was cached"
+        );
+    }
+
+    #[test]
+    fn shares_the_cached_contents() {
+        let path = env::temp_dir().join(format!("web-server-{}-shared.txt", process::id()));
+        fs::write(&path, "0123456789").unwrap();
+
+        let cache = FileCache::new(1024);
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let first = cache.body(&path, modified, 10, File::open(&path).unwrap());
+        let second = cache.body(&path, modified, 10, File::open(&path).unwrap());
+
+        fs::remove_file(&path).unwrap();
+
+        let (Ok(Body::Shared(first)), Ok(Body::Shared(second))) = (first, second) else {
+            panic!("the file was not served from the cache");
+        };
+
+        assert!(
+            Arc::ptr_eq(&first, &second),
+            "the cached contents were copied"
+        );
+        assert_eq!(&*first, b"0123456789", "the contents are different");
+    }
+}
@@ -0,0 +1,285 @@
+use crate::http::{Body, Response, Status};
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io::{self, Seek, SeekFrom},
+    ops::Range,
+};
+
+// more ranges than that are more likely an attack than a download
+const MAX_RANGES: usize = 16;
+
+/// What the `Range` field of a request asks for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Ranges {
+    /// The ranges of the file to send, clipped to its
+    /// length, in the order they were asked for.
+    Satisfiable(Vec<Range<u64>>),
+
+    /// None of the ranges overlap the file.
+    Unsatisfiable,
+}
+
+/// Parses the `Range` field of a request for a file of `length` bytes.
+///
+/// Returns `None` if the field should be ignored, which means the whole
+/// file is sent: if it is invalid, is not in bytes, has too many ranges,
+/// or has ranges that overlap.
+pub fn parse(field: &str, length: u64) -> Option<Ranges> {
+    let (unit, specs) = field.split_once('=')?;
+
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+
+    let mut specs_count = 0;
+    let mut ranges = Vec::new();
+
+    // lists can have empty elements
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        specs_count += 1;
+
+        let (first, last) = spec.split_once('-')?;
+
+        let range = if first.is_empty() {
+            // the last `suffix` bytes
+            let suffix = parse_number(last)?;
+            (suffix > 0 && length > 0).then(|| length - suffix.min(length)..length)
+        } else {
+            let first = parse_number(first)?;
+            let end = if last.is_empty() {
+                length
+            } else {
+                let last = parse_number(last)?;
+
+                if last < first {
+                    return None;
+                }
+
+                last.saturating_add(1).min(length)
+            };
+
+            (first < length).then_some(first..end)
+        };
+
+        ranges.extend(range);
+    }
+
+    if specs_count == 0 || specs_count > MAX_RANGES {
+        return None;
+    }
+
+    if ranges.is_empty() {
+        return Some(Ranges::Unsatisfiable);
+    }
+
+    // overlapping ranges would send the same bytes many times, and RFC 9110
+    // lets servers ignore them rather than coalescing them
+    let mut sorted: Vec<_> = ranges.iter().collect();
+    sorted.sort_unstable_by_key(|range| range.start);
+
+    let overlap = sorted.windows(2).any(|pair| pair[1].start < pair[0].end);
+
+    (!overlap).then_some(Ranges::Satisfiable(ranges))
+}
+
+/// Makes the 206 response sending `ranges` of `body`, the whole file of
+/// `length` bytes. A single range is sent as is, and many ranges as a
+/// `multipart/byteranges` body whose parts have `content_type`.
+///
+/// # Panics
+///
+/// Panics if `body` is already made of ranges.
+pub fn partial_response(
+    body: Body,
+    ranges: &[Range<u64>],
+    content_type: &str,
+    length: u64,
+) -> io::Result<Response> {
+    if let [range] = ranges {
+        let body = match body {
+            Body::Bytes(bytes) => Body::Bytes(bytes[to_indexes(range)].to_vec()),
+            Body::Shared(bytes) => Body::Bytes(bytes[to_indexes(range)].to_vec()),
+            Body::File { mut file, .. } => {
+                file.seek(SeekFrom::Start(range.start))?;
+
+                Body::File {
+                    file,
+                    length: range.end - range.start,
+                }
+            }
+            Body::FileRanges { .. } => panic!("body should be the whole file"),
+        };
+
+        let mut response = Response::new(Status::PartialContent, body);
+        response.headers.add("Content-Type", content_type);
+        response
+            .headers
+            .add("Content-Range", content_range(range, length));
+
+        return Ok(response);
+    }
+
+    // random, so that it is unlikely to be in the file
+    let boundary = format!("{:016x}", RandomState::new().build_hasher().finish());
+
+    let heads = ranges.iter().enumerate().map(|(index, range)| {
+        // the line break before the first boundary is optional
+        let line_break = if index == 0 { "" } else { "\r\n" };
+
+        format!(
+            "{line_break}--{boundary}\r\n\
+             Content-Type: {content_type}\r\n\
+             Content-Range: {}\r\n\
+             \r\n",
+            content_range(range, length)
+        )
+        .into_bytes()
+    });
+
+    let tail = format!("\r\n--{boundary}--\r\n").into_bytes();
+
+    let body = match body {
+        Body::Bytes(bytes) => Body::Bytes(multipart(heads.zip(ranges), tail, &bytes)),
+        Body::Shared(bytes) => Body::Bytes(multipart(heads.zip(ranges), tail, &bytes)),
+        Body::File { file, .. } => Body::FileRanges {
+            file,
+            ranges: heads.zip(ranges.iter().cloned()).collect(),
+            tail,
+        },
+        Body::FileRanges { .. } => panic!("body should be the whole file"),
+    };
+
+    let mut response = Response::new(Status::PartialContent, body);
+    response.headers.add(
+        "Content-Type",
+        format!("multipart/byteranges; boundary={boundary}"),
+    );
+
+    Ok(response)
+}
+
+fn multipart<'a>(
+    parts: impl Iterator<Item = (Vec<u8>, &'a Range<u64>)>,
+    tail: Vec<u8>,
+    bytes: &[u8],
+) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    for (head, range) in parts {
+        body.extend(head);
+        body.extend(&bytes[to_indexes(range)]);
+    }
+
+    body.extend(tail);
+    body
+}
+
+fn content_range(range: &Range<u64>, length: u64) -> String {
+    format!("bytes {}-{}/{length}", range.start, range.end - 1)
+}
+
+// the ranges of a body in memory fit in its indexes
+fn to_indexes(range: &Range<u64>) -> Range<usize> {
+    let index = |position| usize::try_from(position).expect("range should be in memory");
+    index(range.start)..index(range.end)
+}
+
+fn parse_number(number: &str) -> Option<u64> {
+    if !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit()) {
+        number.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn satisfiable(ranges: &[(u64, u64)]) -> Ranges {
+        Ranges::Satisfiable(ranges.iter().map(|&(start, end)| start..end).collect())
+    }
+
+    #[test]
+    fn parses_the_ranges_of_a_file() {
+        let cases = [
+            ("bytes=0-499", Some(satisfiable(&[(0, 500)]))),
+            ("bytes=500-", Some(satisfiable(&[(500, 1000)]))),
+            ("bytes=-200", Some(satisfiable(&[(800, 1000)]))),
+            ("bytes=-2000", Some(satisfiable(&[(0, 1000)]))),
+            ("bytes=900-1999", Some(satisfiable(&[(900, 1000)]))),
+            ("Bytes=0-0, ,-1", Some(satisfiable(&[(0, 1), (999, 1000)]))),
+            ("bytes=0-99,2000-3000", Some(satisfiable(&[(0, 100)]))),
+            ("bytes=1000-", Some(Ranges::Unsatisfiable)),
+            ("bytes=-0", Some(Ranges::Unsatisfiable)),
+            ("bytes=0-9,10-19", Some(satisfiable(&[(0, 10), (10, 20)]))),
+            ("bytes=0-499,400-", None),
+            ("bytes=0-10,5-15", None),
+            ("bytes=-100,0-950", None),
+            ("bytes=0-0,100-199,150-150", None),
+            ("bytes=5-1", None),
+            ("bytes=a-b", None),
+            ("bytes=", None),
+            ("items=0-1", None),
+            ("bytes 0-1", None),
+        ];
+
+        for (field, ranges) in cases {
+            assert_eq!(
+                parse(field, 1000),
+                ranges,
+                "{field:?} was not parsed correctly"
+            );
+        }
+
+        assert_eq!(
+            parse(&format!("bytes={}", ["0-0"; 17].join(",")), 1000),
+            None,
+            "too many ranges were accepted"
+        );
+    }
+
+    #[test]
+    fn sends_many_ranges_as_a_multipart_body() {
+        let response = partial_response(
+            Body::Bytes(b"0123456789".to_vec()),
+            &[0..2, 7..10],
+            "text/plain",
+            10,
+        )
+        .unwrap();
+
+        let content_type = response.headers.get("Content-Type").unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap();
+
+        let Body::Bytes(body) = response.body else {
+            panic!("the ranges were not sent from memory");
+        };
+
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            format!(
+                "--{boundary}\r\n\
+                 Content-Type: text/plain\r\n\
+                 Content-Range: bytes 0-1/10\r\n\
+                 \r\n\
+                 01\r\n\
+                 --{boundary}\r\n\
+                 Content-Type: text/plain\r\n\
+                 Content-Range: bytes 7-9/10\r\n\
+                 \r\n\
+                 789\r\n\
+                 --{boundary}--\r\n"
+            ),
+            "the multipart body is different"
+        );
+    }
+}
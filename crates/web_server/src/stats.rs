@@ -0,0 +1,147 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+// one bucket for every power of two microseconds up to about a minute,
+// plus one bucket for everything longer than that
+const BUCKETS: usize = 28;
+
+/// A snapshot of what a [`ThreadPool`](crate::ThreadPool) is doing,
+/// see [`ThreadPool::stats`](crate::ThreadPool::stats).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// The number of jobs waiting for a free thread.
+    pub queued_jobs: usize,
+
+    /// The number of threads executing a job.
+    pub busy_workers: usize,
+
+    /// The number of threads waiting for a job.
+    pub idle_workers: usize,
+
+    /// The number of jobs that ran to completion
+    /// during the lifetime of the pool.
+    pub completed_jobs: usize,
+
+    /// The number of jobs that panicked during the lifetime of the pool.
+    /// Jobs given to [`ThreadPool::submit`](crate::ThreadPool::submit)
+    /// are not counted, their panics are reported by their handles.
+    pub panicked_jobs: usize,
+
+    /// The number of jobs that were dropped from the queue because
+    /// their [`CancellationToken`](crate::CancellationToken) was cancelled.
+    pub cancelled_jobs: usize,
+
+    /// How long the jobs waited in the queue before being executed.
+    pub queue_wait: Histogram,
+
+    /// How long the jobs took to execute, including the ones that panicked.
+    pub run_time: Histogram,
+}
+
+/// Counts durations in buckets whose bounds grow by powers of two,
+/// from one microsecond up to about a minute.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Histogram {
+    counts: [u64; BUCKETS],
+}
+
+impl Histogram {
+    /// Returns the number of durations counted.
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the upper bound of every bucket together with the number of
+    /// durations that are shorter than it but not shorter than the upper
+    /// bound of the previous bucket. The last bucket has no upper bound.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::num::NonZeroUsize;
+    /// use web_server::ThreadPool;
+    ///
+    /// let tp = ThreadPool::build(NonZeroUsize::new(2usize).unwrap());
+    ///
+    /// for (upper_bound, count) in tp.stats().run_time.buckets() {
+    ///     match upper_bound {
+    ///         Some(upper_bound) => println!("< {upper_bound:?}: {count}"),
+    ///         None => println!("longer: {count}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        self.counts.iter().enumerate().map(|(index, &count)| {
+            let upper_bound = (index < BUCKETS - 1).then(|| Duration::from_micros(1 << index));
+            (upper_bound, count)
+        })
+    }
+}
+
+/// A [`Histogram`] that can be updated from many threads at once.
+#[derive(Debug, Default)]
+pub struct AtomicHistogram {
+    counts: [AtomicU64; BUCKETS],
+}
+
+impl AtomicHistogram {
+    pub fn record(&self, duration: Duration) {
+        self.counts[bucket_index(duration)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Histogram {
+        Histogram {
+            counts: self
+                .counts
+                .each_ref()
+                .map(|count| count.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+// the bucket of a duration is the number of bits needed for its
+// microseconds, so bucket `i` holds durations below 2^i microseconds
+fn bucket_index(duration: Duration) -> usize {
+    let bits = u128::BITS - duration.as_micros().leading_zeros();
+    usize::try_from(bits).map_or(BUCKETS - 1, |bits| bits.min(BUCKETS - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_durations_in_the_right_buckets() {
+        let histogram = AtomicHistogram::default();
+
+        histogram.record(Duration::ZERO);
+        histogram.record(Duration::from_micros(3));
+        histogram.record(Duration::from_micros(4));
+        histogram.record(Duration::from_secs(3600));
+
+        let buckets: Vec<_> = histogram
+            .snapshot()
+            .buckets()
+            .filter(|&(_, count)| count > 0)
+            .collect();
+
+        assert_eq!(
+            buckets,
+            [
+                (Some(Duration::from_micros(1)), 1),
+                (Some(Duration::from_micros(4)), 1),
+                (Some(Duration::from_micros(8)), 1),
+                (None, 1),
+            ],
+            "the durations were not recorded in the right buckets"
+        );
+        assert_eq!(
+            histogram.snapshot().count(),
+            4,
+            "not all durations were counted"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{
+    logger::PoolEvent,
+    panic_policy::PanicPolicy,
+    shared_state::{SharedState, WorkerExit},
+    worker::Worker,
+};
+
+use std::{
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+/// Replaces the workers whose threads died with new workers,
+/// so that the pool keeps its configured number of threads,
+/// and drops the workers that retired.
+#[derive(Debug)]
+pub struct Supervisor {
+    thread: JoinHandle<()>,
+    state: Arc<SharedState>,
+}
+
+impl Supervisor {
+    pub fn new(
+        workers: Arc<Mutex<Vec<Worker>>>,
+        state: Arc<SharedState>,
+        panic_policy: PanicPolicy,
+    ) -> Supervisor {
+        let worker_exits = state.supervise();
+
+        let thread = thread::spawn({
+            let state = Arc::clone(&state);
+
+            move || {
+                for worker_exit in worker_exits {
+                    let mut workers = workers.lock().expect("workers lock should not be poisoned");
+
+                    match worker_exit {
+                        WorkerExit::Died(dead_id) => {
+                            if let Some(index) = workers.iter().position(|w| w.id() == dead_id) {
+                                workers.remove(index).join_dead();
+                            }
+
+                            let new_id = state.next_worker_id();
+                            state.worker_restarted();
+                            workers.push(Worker::new(new_id, Arc::clone(&state), panic_policy));
+
+                            // only now, so that the number of live workers
+                            // never drops to zero while replacing a worker
+                            state.worker_exited();
+
+                            state.log(&PoolEvent::WorkerReplaced {
+                                worker_id: dead_id,
+                                new_worker_id: new_id,
+                            });
+                        }
+                        WorkerExit::Retired(retired_id) => {
+                            // dropping the worker waits for its thread to finish
+                            if let Some(index) = workers.iter().position(|w| w.id() == retired_id) {
+                                drop(workers.remove(index));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Supervisor { thread, state }
+    }
+
+    /// Stops replacing dead workers. The workers that already
+    /// died are still replaced before this returns.
+    pub fn stop(self) {
+        self.state.stop_supervising();
+
+        self.thread
+            .join()
+            .expect("supervisor thread should not panic");
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    any::Any,
+    error::Error,
+    fmt::{self, Display, Formatter},
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// The reason why a job given to
+/// [`ThreadPool::submit`](crate::ThreadPool::submit)
+/// did not produce a value.
+#[derive(Debug)]
+pub enum TaskError {
+    /// The job panicked. Contains the value the job panicked with.
+    Panicked(Box<dyn Any + Send + 'static>),
+
+    /// The job was discarded by a shutdown of the pool before
+    /// it had the chance to execute.
+    Abandoned,
+}
+
+impl TaskError {
+    /// Returns the message the job panicked with, if the
+    /// job panicked with a string.
+    #[must_use]
+    pub fn panic_message(&self) -> Option<&str> {
+        match self {
+            TaskError::Panicked(payload) => payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str)),
+            TaskError::Abandoned => None,
+        }
+    }
+}
+
+impl Display for TaskError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::Panicked(_) => match self.panic_message() {
+                Some(message) => write!(f, "the job panicked: {message}"),
+                None => write!(f, "the job panicked"),
+            },
+            TaskError::Abandoned => write!(f, "the job was abandoned before executing"),
+        }
+    }
+}
+
+impl Error for TaskError {}
+
+#[derive(Debug)]
+struct Slot<R> {
+    result: Mutex<Option<Result<R, TaskError>>>,
+    is_set: Condvar,
+}
+
+impl<R> Slot<R> {
+    fn lock(&self) -> MutexGuard<'_, Option<Result<R, TaskError>>> {
+        self.result
+            .lock()
+            .expect("result lock should not be poisoned")
+    }
+
+    fn set(&self, result: Result<R, TaskError>) {
+        let mut slot = self.lock();
+
+        if slot.is_none() {
+            *slot = Some(result);
+            self.is_set.notify_all();
+        }
+    }
+
+    fn wait_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut result = self.lock();
+
+        while result.is_none() {
+            let now = Instant::now();
+
+            if now >= deadline {
+                return false;
+            }
+
+            result = self
+                .is_set
+                .wait_timeout(result, deadline - now)
+                .expect("result lock should not be poisoned")
+                .0;
+        }
+
+        true
+    }
+
+    fn take(&self) -> Result<R, TaskError> {
+        let mut result = self.lock();
+
+        loop {
+            if let Some(result) = result.take() {
+                return result;
+            }
+
+            result = self
+                .is_set
+                .wait(result)
+                .expect("result lock should not be poisoned");
+        }
+    }
+}
+
+/// Allows waiting for the value returned by a job given to
+/// [`ThreadPool::submit`](crate::ThreadPool::submit).
+#[derive(Debug)]
+pub struct TaskHandle<R> {
+    slot: Arc<Slot<R>>,
+}
+
+impl<R> TaskHandle<R> {
+    /// Checks if the job has finished, either by returning a value,
+    /// by panicking or by being abandoned. Does not block.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.slot.lock().is_some()
+    }
+
+    /// Blocks until the job has finished or until `timeout` elapses.
+    ///
+    /// Returns `true` if the job has finished, in which case
+    /// [`TaskHandle::join`] will not block.
+    #[must_use]
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.slot.wait_timeout(timeout)
+    }
+
+    /// Blocks until the job has finished and returns its result.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TaskError::Panicked`] if the job panicked and
+    /// [`TaskError::Abandoned`] if the job never got to execute.
+    pub fn join(self) -> Result<R, TaskError> {
+        self.slot.take()
+    }
+}
+
+/// The sending half of a [`TaskHandle`]. If it gets dropped
+/// without being run, the handle reports [`TaskError::Abandoned`].
+#[derive(Debug)]
+pub struct Completer<R> {
+    slot: Arc<Slot<R>>,
+}
+
+impl<R> Completer<R> {
+    pub fn run(self, f: impl FnOnce() -> R) {
+        let result = panic::catch_unwind(AssertUnwindSafe(f)).map_err(TaskError::Panicked);
+        self.slot.set(result);
+    }
+}
+
+impl<R> Drop for Completer<R> {
+    fn drop(&mut self) {
+        self.slot.set(Err(TaskError::Abandoned));
+    }
+}
+
+pub fn new<R>() -> (TaskHandle<R>, Completer<R>) {
+    let slot = Arc::new(Slot {
+        result: Mutex::new(None),
+        is_set: Condvar::new(),
+    });
+
+    (
+        TaskHandle {
+            slot: Arc::clone(&slot),
+        },
+        Completer { slot },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn join_returns_the_value_of_the_job() {
+        let (handle, completer) = new();
+        completer.run(|| 42);
+
+        assert!(handle.is_finished(), "the handle was not finished");
+        assert_eq!(handle.join().unwrap(), 42, "the value was not returned");
+    }
+
+    #[test]
+    fn join_reports_the_panic_of_the_job() {
+        let (handle, completer) = new::<()>();
+        completer.run(|| panic!("oh no"));
+
+        let error = handle.join().unwrap_err();
+
+        assert!(
+            matches!(error, TaskError::Panicked(_)),
+            "the panic was not reported"
+        );
+        assert_eq!(
+            error.panic_message(),
+            Some("oh no"),
+            "the panic message was not kept"
+        );
+    }
+
+    #[test]
+    fn join_reports_abandoned_jobs() {
+        let (handle, completer) = new::<()>();
+        drop(completer);
+
+        assert!(
+            matches!(handle.join(), Err(TaskError::Abandoned)),
+            "the abandoned job was not reported"
+        );
+    }
+
+    #[test]
+    fn wait_timeout_returns_false_for_unfinished_jobs() {
+        let (handle, completer) = new::<()>();
+
+        assert!(!handle.is_finished(), "the handle was finished");
+        assert!(
+            !handle.wait_timeout(Duration::from_millis(10)),
+            "the wait did not time out"
+        );
+
+        let thread = thread::spawn(move || completer.run(|| {}));
+
+        assert!(
+            handle.wait_timeout(Duration::from_secs(60)),
+            "the wait timed out"
+        );
+
+        thread.join().unwrap();
+    }
+}
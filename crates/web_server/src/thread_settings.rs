@@ -0,0 +1,61 @@
+use std::{
+    fmt::{self, Debug, Formatter},
+    io,
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+type Hook = Arc<dyn Fn(usize) + Send + Sync>;
+
+/// How the threads of a pool are spawned, see the thread settings
+/// of [`ThreadPoolBuilder`](crate::ThreadPoolBuilder).
+#[derive(Clone, Default)]
+pub struct ThreadSettings {
+    pub name_prefix: Option<String>,
+    pub stack_size: Option<usize>,
+    pub on_start: Option<Hook>,
+    pub on_stop: Option<Hook>,
+}
+
+impl ThreadSettings {
+    /// Spawns the thread of the worker with `worker_id`.
+    pub fn spawn<F>(&self, worker_id: usize, f: F) -> io::Result<JoinHandle<()>>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut builder = thread::Builder::new();
+
+        if let Some(name_prefix) = &self.name_prefix {
+            builder = builder.name(format!("{name_prefix}{worker_id}"));
+        }
+
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+
+        builder.spawn(f)
+    }
+
+    pub fn thread_started(&self, worker_id: usize) {
+        if let Some(on_start) = &self.on_start {
+            on_start(worker_id);
+        }
+    }
+
+    pub fn thread_stopping(&self, worker_id: usize) {
+        if let Some(on_stop) = &self.on_stop {
+            on_stop(worker_id);
+        }
+    }
+}
+
+impl Debug for ThreadSettings {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadSettings")
+            .field("name_prefix", &self.name_prefix)
+            .field("stack_size", &self.stack_size)
+            .field("on_start", &self.on_start.as_ref().map(|_| ".."))
+            .field("on_stop", &self.on_stop.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
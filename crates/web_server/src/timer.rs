@@ -0,0 +1,235 @@
+use crate::{
+    cancellation::CancellationToken, scheduler::JobOptions, shared_state::SharedState,
+    types_traits::Job,
+};
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    fmt::{self, Debug, Formatter},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Cancels a job scheduled with [`ThreadPool::execute_after`](crate::ThreadPool::execute_after)
+/// or [`ThreadPool::execute_every`](crate::ThreadPool::execute_every).
+///
+/// Dropping the handle does not cancel the job.
+#[derive(Clone, Debug)]
+pub struct ScheduledHandle(CancellationToken);
+
+impl ScheduledHandle {
+    /// Makes sure that the job does not run anymore. A run
+    /// that already started is not interrupted.
+    pub fn cancel(&self) {
+        self.0.cancel();
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.is_cancelled()
+    }
+}
+
+enum Task {
+    Once(Job),
+    Every {
+        interval: Duration,
+        f: Arc<dyn Fn() + Send + Sync>,
+    },
+}
+
+struct Entry {
+    due: Instant,
+    // breaks ties between entries that are due at the same time
+    sequence: u64,
+    task: Task,
+    token: CancellationToken,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Entry) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Entry) -> Ordering {
+        (self.due, self.sequence).cmp(&(other.due, other.sequence))
+    }
+}
+
+#[derive(Default)]
+struct Entries {
+    heap: BinaryHeap<Reverse<Entry>>,
+    next_sequence: u64,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct TimerState {
+    entries: Mutex<Entries>,
+    changed: Condvar,
+}
+
+/// Queues jobs on a pool once they are due, from a single thread.
+pub struct Timer {
+    thread: Option<JoinHandle<()>>,
+    state: Arc<TimerState>,
+}
+
+impl Debug for Timer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timer")
+            .field("thread", &self.thread)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Timer {
+    pub fn new(pool_state: Arc<SharedState>) -> Timer {
+        let state = Arc::new(TimerState::default());
+
+        let thread = thread::spawn({
+            let state = Arc::clone(&state);
+            move || run(&state, &pool_state)
+        });
+
+        Timer {
+            thread: Some(thread),
+            state,
+        }
+    }
+
+    pub fn execute_after(&self, delay: Duration, job: Job) -> ScheduledHandle {
+        self.schedule(Instant::now() + delay, Task::Once(job))
+    }
+
+    pub fn execute_every(
+        &self,
+        interval: Duration,
+        f: Arc<dyn Fn() + Send + Sync>,
+    ) -> ScheduledHandle {
+        self.schedule(Instant::now() + interval, Task::Every { interval, f })
+    }
+
+    fn schedule(&self, due: Instant, task: Task) -> ScheduledHandle {
+        let token = CancellationToken::new();
+        let mut entries = lock(&self.state.entries);
+
+        let sequence = entries.next_sequence;
+        entries.next_sequence += 1;
+
+        entries.heap.push(Reverse(Entry {
+            due,
+            sequence,
+            task,
+            token: token.clone(),
+        }));
+
+        self.state.changed.notify_one();
+        ScheduledHandle(token)
+    }
+
+    /// Drops all the scheduled jobs and waits for the timer thread to exit.
+    pub fn stop(&mut self) {
+        lock(&self.state.entries).closed = true;
+        self.state.changed.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().expect("timer thread should not panic");
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn run(state: &TimerState, pool_state: &SharedState) {
+    let mut entries = lock(&state.entries);
+
+    loop {
+        if entries.closed {
+            return;
+        }
+
+        let now = Instant::now();
+
+        let Some(Reverse(next)) = entries.heap.peek() else {
+            entries = state
+                .changed
+                .wait(entries)
+                .expect("timer lock should not be poisoned");
+
+            continue;
+        };
+
+        if next.due > now {
+            let timeout = next.due - now;
+
+            entries = state
+                .changed
+                .wait_timeout(entries, timeout)
+                .expect("timer lock should not be poisoned")
+                .0;
+
+            continue;
+        }
+
+        let Reverse(Entry {
+            due,
+            sequence,
+            task,
+            token,
+        }) = entries.heap.pop().expect("heap should not be empty");
+
+        if token.is_cancelled() {
+            continue;
+        }
+
+        let job: Job = match task {
+            Task::Once(job) => job,
+            Task::Every { interval, f } => {
+                let job_f = Arc::clone(&f);
+
+                // falls behind instead of running many times in a row
+                // when the pool could not keep up with the interval
+                entries.heap.push(Reverse(Entry {
+                    due: (due + interval).max(now),
+                    sequence,
+                    task: Task::Every { interval, f },
+                    token: token.clone(),
+                }));
+
+                Box::new(move || job_f())
+            }
+        };
+
+        let options = JobOptions {
+            token: Some(token),
+            ..JobOptions::default()
+        };
+
+        // the lock is released so that scheduling does not wait
+        // for a full queue of the pool to make room
+        drop(entries);
+        pool_state.scheduler().push(job, &options);
+        entries = lock(&state.entries);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().expect("timer lock should not be poisoned")
+}
@@ -1,4 +1,17 @@
-pub trait FnOnceSend: FnOnce() + Send + 'static {}
-impl<T> FnOnceSend for T where T: FnOnce() + Send + 'static {}
+use std::any::Any;
+
+pub trait FnOnceSend: FnOnce() + Send + 'static {
+    /// Allows getting back the concrete type of a [`Job`].
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send>;
+}
+
+impl<T> FnOnceSend for T
+where
+    T: FnOnce() + Send + 'static,
+{
+    fn into_any(self: Box<Self>) -> Box<dyn Any + Send> {
+        self
+    }
+}
 
 pub type Job = Box<dyn FnOnceSend>;
@@ -1,11 +1,17 @@
 use crate::{
-    panic_unwind::Callable,
-    types_traits::{FnOnceSend, Job},
+    logger::PoolEvent,
+    panic_policy::PanicPolicy,
+    panic_unwind::{self, Callable},
+    scheduler::{Next, QueuedJob},
+    shared_state::{SharedState, WorkerExit},
+    types_traits::FnOnceSend,
 };
 
 use std::{
-    sync::{mpsc::Receiver, Arc, Mutex},
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
     thread::{self, JoinHandle},
+    time::Instant,
 };
 
 #[derive(Debug)]
@@ -15,29 +21,89 @@ pub struct Worker {
 }
 
 impl Worker {
-    pub fn new(
+    pub fn new(id: usize, state: Arc<SharedState>, panic_policy: PanicPolicy) -> Worker {
+        match panic_policy {
+            PanicPolicy::Abort => Worker::spawn(id, state, false, panic_unwind::abort_process),
+            PanicPolicy::RespawnWorker => Worker::spawn(id, state, false, || {}),
+            PanicPolicy::CatchAndContinue => Worker::spawn(id, state, true, || {}),
+        }
+    }
+
+    #[cfg(test)] // used by tests that need a custom on_panic_unwind
+    pub fn with_on_panic_unwind(
+        id: usize,
+        state: Arc<SharedState>,
+        on_panic_unwind: impl FnOnceSend,
+    ) -> Worker {
+        Worker::spawn(id, state, false, on_panic_unwind)
+    }
+
+    fn spawn(
         id: usize,
-        receiver: Arc<Mutex<Receiver<Job>>>,
+        state: Arc<SharedState>,
+        catch_panics: bool,
         on_panic_unwind: impl FnOnceSend,
     ) -> Worker {
-        let thread = thread::spawn(move || {
-            let _on_panic_unwind = Callable::new(on_panic_unwind);
-
-            loop {
-                let message = receiver
-                    .lock()
-                    .expect("receiver lock should not be poisoned")
-                    .recv();
-
-                if let Ok(job) = message {
-                    println!("Worker {id} got a job. Executing.");
-                    job();
-                } else {
-                    println!("Worker {id} disconnected; shutting down.");
-                    break;
+        // registered before spawning so that a shutdown that happens
+        // right after this call still waits for the new thread
+        state.worker_started();
+
+        let thread_settings = state.thread_settings().clone();
+
+        let thread = thread_settings
+            .spawn(id, move || {
+                let mut on_exit = ExitGuard {
+                    id,
+                    state: Arc::clone(&state),
+                    retired: false,
+                };
+                state.thread_settings().thread_started(id);
+
+                let _on_panic_unwind = Callable::new(on_panic_unwind);
+                let registration = state.scheduler().register();
+
+                state.log(&PoolEvent::WorkerStarted { worker_id: id });
+
+                loop {
+                    match state
+                        .scheduler()
+                        .next_job(&registration, state.idle_timeout())
+                    {
+                        Next::Job(QueuedJob {
+                            id: job_id,
+                            job,
+                            queued_at,
+                            ..
+                        }) => {
+                            if catch_panics {
+                                // the panic is logged by the job guard
+                                let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+                                    let _job_guard = JobGuard::new(&state, id, job_id, queued_at);
+                                    job();
+                                }));
+                            } else {
+                                let _job_guard = JobGuard::new(&state, id, job_id, queued_at);
+                                job();
+                            }
+                        }
+                        Next::Idle => {
+                            if state.try_shrink() {
+                                on_exit.retired = true;
+                                break;
+                            }
+                        }
+                        Next::Retire => {
+                            on_exit.retired = true;
+                            break;
+                        }
+                        Next::Closed => {
+                            state.log(&PoolEvent::WorkerStopped { worker_id: id });
+                            break;
+                        }
+                    }
                 }
-            }
-        });
+            })
+            .expect("worker thread should be spawned");
 
         Worker {
             id,
@@ -45,22 +111,126 @@ impl Worker {
         }
     }
 
-    #[cfg(test)] // currently used only by tests
     pub fn id(&self) -> usize {
         self.id
     }
-}
 
-impl Drop for Worker {
-    fn drop(&mut self) {
-        println!("Shutting down worker {}.", self.id);
+    pub fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .expect("thread should not be None")
+            .is_finished()
+    }
 
-        #[allow(unused_variables)]
-        let join_result = self
+    /// Lets the worker thread run to completion on its own instead of
+    /// waiting for it like [`Drop`] does.
+    pub fn detach(mut self) {
+        self.thread.take();
+    }
+
+    /// Waits for the thread of a worker that is known to have died.
+    pub fn join_dead(mut self) {
+        // the panic was already reported by the panic hook
+        let _ = self
             .thread
             .take()
             .expect("thread should not be None")
             .join();
+    }
+}
+
+struct ExitGuard {
+    id: usize,
+    state: Arc<SharedState>,
+    retired: bool,
+}
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        self.state.thread_settings().thread_stopping(self.id);
+
+        if thread::panicking() {
+            self.state
+                .log(&PoolEvent::WorkerDied { worker_id: self.id });
+
+            // a supervisor that replaces the worker also marks it as exited
+            if self.state.report_worker_exit(WorkerExit::Died(self.id)) {
+                return;
+            }
+        } else if self.retired {
+            self.state
+                .log(&PoolEvent::WorkerRetired { worker_id: self.id });
+
+            // the supervisor drops the retired worker; if nobody is
+            // supervising anymore, the pool is being shut down and
+            // drops all of its workers anyway
+            self.state.report_worker_exit(WorkerExit::Retired(self.id));
+        }
+
+        self.state.worker_exited();
+    }
+}
+
+struct JobGuard<'a> {
+    state: &'a SharedState,
+    worker_id: usize,
+    job_id: u64,
+    started_at: Instant,
+}
+
+impl JobGuard<'_> {
+    fn new(state: &SharedState, worker_id: usize, job_id: u64, queued_at: Instant) -> JobGuard<'_> {
+        let started_at = Instant::now();
+        let queue_wait = started_at.saturating_duration_since(queued_at);
+
+        state.job_started(queue_wait);
+        state.log(&PoolEvent::JobStarted {
+            worker_id,
+            job_id,
+            queue_wait,
+        });
+
+        JobGuard {
+            state,
+            worker_id,
+            job_id,
+            started_at,
+        }
+    }
+}
+
+impl Drop for JobGuard<'_> {
+    fn drop(&mut self) {
+        let run_time = self.started_at.elapsed();
+
+        let (worker_id, job_id) = (self.worker_id, self.job_id);
+
+        if thread::panicking() {
+            self.state.job_panicked(run_time);
+            self.state.log(&PoolEvent::JobPanicked {
+                worker_id,
+                job_id,
+                run_time,
+            });
+        } else {
+            self.state.job_finished(run_time);
+            self.state.log(&PoolEvent::JobFinished {
+                worker_id,
+                job_id,
+                run_time,
+            });
+        }
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+
+        #[allow(unused_variables)]
+        let join_result = thread.join();
 
         #[cfg(not(test))] // when testing, we sometimes want to run code that panics
         join_result.expect("worker thread should not panic");
@@ -70,31 +240,42 @@ impl Drop for Worker {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::panic_unwind;
+    use crate::{scheduler::JobOptions, types_traits::Job, ThreadPoolBuilder};
+
+    use std::{
+        env,
+        num::NonZeroUsize,
+        process::{Command, Stdio},
+        sync::{mpsc, OnceLock},
+    };
 
-    use std::sync::{mpsc, OnceLock};
+    fn new_shared_state() -> Arc<SharedState> {
+        Arc::new(SharedState::new(&ThreadPoolBuilder::new(NonZeroUsize::MIN)))
+    }
+
+    fn send(state: &SharedState, job: Job) {
+        state.scheduler().push(job, &JobOptions::default());
+    }
 
     #[test]
     fn new_creates_a_proper_worker() {
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        let state = new_shared_state();
 
-        let worker = Worker::new(15, receiver, panic_unwind::abort_process);
+        let worker = Worker::new(15, Arc::clone(&state), PanicPolicy::Abort);
         assert_eq!(worker.id(), 15, "worker id is different");
         assert!(
             worker.thread.is_some(),
             "no worker thread was assigned to the worker"
         );
 
-        drop(sender);
+        state.scheduler().close();
     }
 
     #[test]
     fn executes_code_on_another_thread() {
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        let state = new_shared_state();
 
-        let worker = Worker::new(0, receiver, panic_unwind::abort_process);
+        let worker = Worker::new(0, Arc::clone(&state), PanicPolicy::Abort);
 
         let this_thread_id = thread::current().id();
         let worker_thread_id = Arc::new(OnceLock::new());
@@ -104,12 +285,9 @@ mod tests {
             worker_thread_id_clone.get_or_init(|| thread::current().id());
         });
 
-        assert!(
-            sender.send(get_worker_thread_id).is_ok(),
-            "failed to get the worker thread id"
-        );
+        send(&state, get_worker_thread_id);
 
-        drop(sender);
+        state.scheduler().close();
         drop(worker);
 
         assert!(
@@ -126,8 +304,7 @@ mod tests {
 
     #[test]
     fn calls_on_panic_unwind_when_thread_panics() {
-        let (sender, receiver) = mpsc::channel();
-        let receiver = Arc::new(Mutex::new(receiver));
+        let state = new_shared_state();
         let was_called = Arc::new(OnceLock::new());
         let was_called_clone = Arc::clone(&was_called);
 
@@ -135,15 +312,15 @@ mod tests {
             was_called_clone.get_or_init(|| true);
         };
 
-        let worker = Worker::new(0, receiver, on_panic_unwind);
+        let worker = Worker::with_on_panic_unwind(0, Arc::clone(&state), on_panic_unwind);
 
         let panic = Box::new(|| {
             panic!();
         });
 
-        assert!(sender.send(panic).is_ok(), "failed to send a panic message");
+        send(&state, panic);
 
-        drop(sender);
+        state.scheduler().close();
         drop(worker);
 
         assert!(
@@ -151,4 +328,121 @@ mod tests {
             "on_panic_unwind was not called"
         );
     }
+
+    #[test]
+    fn reports_its_death_when_thread_panics() {
+        let state = new_shared_state();
+        let worker_exits = state.supervise();
+
+        let worker = Worker::new(7, Arc::clone(&state), PanicPolicy::RespawnWorker);
+
+        send(&state, Box::new(|| panic!()));
+
+        assert_eq!(
+            worker_exits.recv().ok(),
+            Some(WorkerExit::Died(7)),
+            "the death of the worker was not reported"
+        );
+
+        worker.join_dead();
+    }
+
+    #[test]
+    fn reports_its_retirement_when_retired() {
+        let state = new_shared_state();
+        let worker_exits = state.supervise();
+
+        let worker = Worker::new(3, Arc::clone(&state), PanicPolicy::Abort);
+
+        state.scheduler().retire_workers(1);
+
+        assert_eq!(
+            worker_exits.recv().ok(),
+            Some(WorkerExit::Retired(3)),
+            "the retirement was not reported"
+        );
+
+        drop(worker);
+    }
+
+    #[test]
+    fn keeps_thread_when_job_panics() {
+        let state = new_shared_state();
+        let worker = Worker::new(0, Arc::clone(&state), PanicPolicy::CatchAndContinue);
+
+        let (thread_id_sender, thread_id_receiver) = mpsc::channel();
+        let thread_id_sender_clone = thread_id_sender.clone();
+
+        let panic = Box::new(move || {
+            thread_id_sender_clone.send(thread::current().id()).unwrap();
+            panic!();
+        });
+
+        let get_worker_thread_id = Box::new(move || {
+            thread_id_sender.send(thread::current().id()).unwrap();
+        });
+
+        send(&state, panic);
+        send(&state, get_worker_thread_id);
+
+        state.scheduler().close();
+        drop(worker);
+
+        let panicked_thread_id = thread_id_receiver.recv().unwrap();
+        let next_thread_id = thread_id_receiver.recv().ok();
+
+        assert!(
+            next_thread_id.is_some(),
+            "the job after the panic was not executed"
+        );
+        assert_eq!(
+            next_thread_id.unwrap(),
+            panicked_thread_id,
+            "the job after the panic was not executed on the thread that panicked"
+        );
+    }
+
+    // the test runs itself in a child process, which is the one that should be aborted
+    #[test]
+    fn aborts_the_process_when_job_panics() {
+        const CHILD: &str = "WEB_SERVER_ABORT_CHILD";
+
+        if env::var_os(CHILD).is_some() {
+            let state = new_shared_state();
+            let worker = Worker::new(0, Arc::clone(&state), PanicPolicy::Abort);
+
+            send(&state, Box::new(|| panic!()));
+
+            state.scheduler().close();
+            drop(worker);
+            return;
+        }
+
+        let status = Command::new(env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "worker::tests::aborts_the_process_when_job_panics",
+            ])
+            .env(CHILD, "1")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+
+            const SIGABRT: i32 = 6;
+
+            assert_eq!(
+                status.signal(),
+                Some(SIGABRT),
+                "the process was not aborted"
+            );
+        }
+
+        #[cfg(not(unix))]
+        assert!(!status.success(), "the process was not aborted");
+    }
 }
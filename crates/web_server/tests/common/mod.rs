@@ -0,0 +1,76 @@
+use std::{
+    io::{BufRead, BufReader, Read},
+    net::SocketAddr,
+    process::{Child, ChildStdout, Command, Output, Stdio},
+    thread::{self, JoinHandle},
+};
+
+/// A running web server, listening on a port chosen by the system so that
+/// no other process can take it between choosing and binding it.
+pub struct Server {
+    process: Child,
+    stdout: BufReader<ChildStdout>,
+    stderr: JoinHandle<String>,
+    pub address: SocketAddr,
+}
+
+impl Server {
+    pub fn start(args: &[&str]) -> Server {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_web-server"))
+            .args(["--port", "0"])
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let mut stdout = BufReader::new(process.stdout.take().unwrap());
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+
+        let address = line
+            .strip_prefix("Listening on ")
+            .and_then(|line| line.strip_suffix(".\n"))
+            .unwrap_or_else(|| panic!("the server did not tell its address: {line:?}"))
+            .parse()
+            .unwrap();
+
+        // read all along, so that the server never blocks on a full pipe
+        let mut stderr = process.stderr.take().unwrap();
+        let stderr = thread::spawn(move || {
+            let mut output = String::new();
+            stderr.read_to_string(&mut output).unwrap();
+            output
+        });
+
+        Server {
+            process,
+            stdout,
+            stderr,
+            address,
+        }
+    }
+
+    pub fn terminate(&self) {
+        let kill_status = Command::new("kill")
+            .args(["-TERM", &self.process.id().to_string()])
+            .status()
+            .unwrap();
+
+        assert!(kill_status.success(), "the signal was not sent");
+    }
+
+    /// Waits for the server to exit, with what it printed after its address.
+    pub fn wait(mut self) -> Output {
+        let status = self.process.wait().unwrap();
+
+        let mut stdout = Vec::new();
+        self.stdout.read_to_end(&mut stdout).unwrap();
+
+        Output {
+            status,
+            stdout,
+            stderr: self.stderr.join().unwrap().into_bytes(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Server;
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn sheds_the_connections_over_the_limit_with_503() {
+    let server = Server::start(&["--workers=1", "--max-connections=1", "--retry-after=2"]);
+
+    let mut busy_stream = TcpStream::connect(server.address).unwrap();
+
+    busy_stream
+        .write_all(b"GET /sleep HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let mut shed_stream = TcpStream::connect(server.address).unwrap();
+    shed_stream
+        .set_read_timeout(Some(Duration::from_secs(2)))
+        .unwrap();
+
+    // answered before the request is even read, so it does not need to be sent
+    let mut response = String::new();
+    shed_stream.read_to_string(&mut response).unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 503 SERVICE UNAVAILABLE"),
+        "the connection over the limit was not shed"
+    );
+    assert!(
+        response.contains("Retry-After: 2\r\n"),
+        "the client was not told when to retry"
+    );
+
+    server.terminate();
+
+    let mut response = String::new();
+    busy_stream.read_to_string(&mut response).unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 200 OK"),
+        "the connection within the limit was not answered"
+    );
+
+    let output = server.wait();
+
+    assert!(output.status.success(), "the server exited with a failure");
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Connections shed while overloaded: 1."),
+        "the shed connections were not counted"
+    );
+}
@@ -0,0 +1,43 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Server;
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+#[test]
+fn answers_a_header_sent_too_slowly_with_408() {
+    let server = Server::start(&["--header-timeout=1"]);
+
+    let mut stream = TcpStream::connect(server.address).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+
+    // the header is never finished
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n")
+        .unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 408 REQUEST TIMEOUT"),
+        "the slow client was not told that it timed out: {response:?}"
+    );
+    assert!(
+        response.contains("Connection: close\r\n"),
+        "the connection was not closed after the timeout"
+    );
+
+    server.terminate();
+
+    let output = server.wait();
+
+    assert!(output.status.success(), "the server exited with a failure");
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Server;
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn finishes_the_requests_in_flight_on_sigterm() {
+    let server = Server::start(&[]);
+    let mut stream = TcpStream::connect(server.address).unwrap();
+
+    // answered first, so the connection is surely handled by a worker
+    stream
+        .write_all(b"GET /hello/test HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+
+    // the head and the body can arrive separately
+    let mut response = Vec::new();
+
+    while !response.ends_with(b"Hello, test!") {
+        let mut buf = [0; 512];
+        let length = stream.read(&mut buf).unwrap();
+
+        assert!(length > 0, "the server did not answer the first request");
+        response.extend_from_slice(&buf[..length]);
+    }
+
+    stream
+        .write_all(b"GET /sleep HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+
+    thread::sleep(Duration::from_millis(200));
+
+    server.terminate();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 200 OK"),
+        "the request in flight was not answered"
+    );
+    assert!(
+        response.contains("Connection: close"),
+        "the connection was not closed after the request in flight"
+    );
+
+    let address = server.address;
+    let output = server.wait();
+
+    assert!(output.status.success(), "the server exited with a failure");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Shutting down.\n",
+        "the shutdown was not logged"
+    );
+    assert!(
+        TcpStream::connect(address).is_err(),
+        "the server still accepts connections"
+    );
+}
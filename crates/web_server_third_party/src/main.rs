@@ -1,14 +1,24 @@
+mod signals;
+
 use rayon::{ThreadBuilder, ThreadPool, ThreadPoolBuilder};
+use signals::Signals;
 
 use std::{
-    fs,
+    env, fs,
     io::{BufRead, BufReader, Write},
-    net::{TcpListener, TcpStream},
-    process::ExitCode,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream},
+    process::{self, ExitCode},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+// how long the connections in flight can take to finish on SIGINT or SIGTERM
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 fn main() -> ExitCode {
     if let Err(e) = execute() {
         eprintln!("{e}");
@@ -19,28 +29,147 @@ fn main() -> ExitCode {
 }
 
 fn execute() -> Result<(), String> {
-    let listener = TcpListener::bind("127.0.0.1:7878")
-        .map_err(|e| format!("Could not bind the socket: {e}."))?;
+    // the address to listen on can be given as the only argument
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:7878".to_string());
+
+    let listener =
+        TcpListener::bind(address).map_err(|e| format!("Could not bind the socket: {e}."))?;
+
+    let local_address = listener
+        .local_addr()
+        .map_err(|e| format!("Could not get the address of the socket: {e}."))?;
+
+    // the port can be chosen by the system, so this is where to find the server
+    println!("Listening on {local_address}.");
+
+    let signals = Signals::register()
+        .map_err(|e| format!("Could not register the shutdown signals: {e}."))?;
+
+    let shutting_down = Arc::new(AtomicBool::new(false));
+    let in_flight = Arc::new(InFlight::default());
+
+    wait_for_shutdown_signals(signals, Arc::clone(&shutting_down), local_address);
 
     ThreadPoolBuilder::new()
         .num_threads(4)
         .build_scoped(ThreadBuilder::run, |pool| {
-            listen_for_connections(&listener, pool);
+            listen_for_connections(&listener, pool, &shutting_down, &in_flight);
             println!("Shutting down.");
+
+            // the pool would wait for the unfinished connections when dropped
+            if !in_flight.wait_until_finished(Instant::now() + SHUTDOWN_TIMEOUT) {
+                eprintln!("Could not finish the connections before the shutdown timeout.");
+                process::exit(1);
+            }
         })
-        .map_err(|e| format!("Could not build the thread pool: {e}."))?;
+        .map_err(|e| format!("Could not build the thread pool: {e}."))
+}
 
-    Ok(())
+// stops the accept loop on the first signal and exits right away on the second one
+fn wait_for_shutdown_signals(
+    mut signals: Signals,
+    shutting_down: Arc<AtomicBool>,
+    local_address: SocketAddr,
+) {
+    // the accept loop is woken up by connecting to it
+    let wake_up_address = match local_address.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => (Ipv4Addr::LOCALHOST, local_address.port()).into(),
+        IpAddr::V6(ip) if ip.is_unspecified() => (Ipv6Addr::LOCALHOST, local_address.port()).into(),
+        _ => local_address,
+    };
+
+    thread::spawn(move || {
+        if let Err(e) = signals.wait() {
+            eprintln!("Could not wait for the shutdown signals: {e}.");
+            return;
+        }
+
+        shutting_down.store(true, Ordering::SeqCst);
+
+        if let Err(e) = TcpStream::connect(wake_up_address) {
+            eprintln!("Could not wake up the accept loop: {e}.");
+        }
+
+        if signals.wait().is_ok() {
+            eprintln!("Exiting without waiting for the connections.");
+            process::exit(1);
+        }
+    });
+}
+
+// counts the connections being handled, so that the shutdown can wait for them
+#[derive(Default)]
+struct InFlight {
+    connections: Mutex<usize>,
+    finished: Condvar,
 }
 
-fn listen_for_connections(listener: &TcpListener, pool: &ThreadPool) {
-    for stream in listener.incoming().take(2) {
+impl InFlight {
+    fn start(self: &Arc<InFlight>) -> InFlightConnection {
+        *self.lock() += 1;
+        InFlightConnection(Arc::clone(self))
+    }
+
+    // returns `false` if some connections are still in flight at `deadline`
+    fn wait_until_finished(&self, deadline: Instant) -> bool {
+        let mut connections = self.lock();
+
+        while *connections > 0 {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+
+            if timeout.is_zero() {
+                return false;
+            }
+
+            connections = self
+                .finished
+                .wait_timeout(connections, timeout)
+                .expect("connections lock should not be poisoned")
+                .0;
+        }
+
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        self.connections
+            .lock()
+            .expect("connections lock should not be poisoned")
+    }
+}
+
+struct InFlightConnection(Arc<InFlight>);
+
+impl Drop for InFlightConnection {
+    fn drop(&mut self) {
+        *self.0.lock() -= 1;
+        self.0.finished.notify_all();
+    }
+}
+
+fn listen_for_connections(
+    listener: &TcpListener,
+    pool: &ThreadPool,
+    shutting_down: &AtomicBool,
+    in_flight: &Arc<InFlight>,
+) {
+    for stream in listener.incoming() {
+        if shutting_down.load(Ordering::SeqCst) {
+            break;
+        }
+
         match stream {
             Ok(stream) => {
-                pool.spawn(|| {
+                let in_flight_connection = in_flight.start();
+
+                pool.spawn(move || {
                     if let Err(e) = handle_connection(stream) {
                         eprintln!("{e}");
                     }
+
+                    drop(in_flight_connection);
                 });
             }
             Err(e) => {
@@ -0,0 +1,79 @@
+//! Waits for SIGINT and SIGTERM, which std cannot do on its own.
+//!
+//! On unix, `signal-hook` forwards the signals to [`Signals::wait`], so no
+//! code of the server runs in a signal handler. On platforms without these
+//! signals, waiting blocks forever.
+
+use std::io;
+
+/// A signal asking the server to shut down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Interrupt,
+    Terminate,
+}
+
+/// The registered shutdown signals, see [`Signals::register`].
+#[derive(Debug)]
+pub struct Signals(imp::Receiver);
+
+impl Signals {
+    /// Starts catching SIGINT and SIGTERM, so that they are no longer fatal
+    /// and can be waited for with [`Signals::wait`].
+    pub fn register() -> io::Result<Signals> {
+        imp::register().map(Signals)
+    }
+
+    /// Blocks until SIGINT or SIGTERM is sent to the process.
+    pub fn wait(&mut self) -> io::Result<Signal> {
+        imp::wait(&mut self.0)
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use super::Signal;
+
+    use signal_hook::{
+        consts::{SIGINT, SIGTERM},
+        iterator::Signals,
+    };
+    use std::io;
+
+    pub type Receiver = Signals;
+
+    pub fn register() -> io::Result<Receiver> {
+        Signals::new([SIGINT, SIGTERM])
+    }
+
+    pub fn wait(signals: &mut Receiver) -> io::Result<Signal> {
+        loop {
+            match signals.forever().next() {
+                Some(SIGINT) => return Ok(Signal::Interrupt),
+                Some(SIGTERM) => return Ok(Signal::Terminate),
+                Some(_) => {}
+                None => return Err(io::Error::other("the signals are no longer delivered")),
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use super::Signal;
+
+    use std::{io, thread};
+
+    #[derive(Debug)]
+    pub struct Receiver;
+
+    pub fn register() -> io::Result<Receiver> {
+        Ok(Receiver)
+    }
+
+    pub fn wait(_: &mut Receiver) -> io::Result<Signal> {
+        loop {
+            thread::park();
+        }
+    }
+}
@@ -0,0 +1,87 @@
+#![cfg(unix)]
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpStream},
+    process::{Child, ChildStdout, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+// on a port chosen by the system, so that no other process can take it in the meantime
+fn start_server() -> (Child, BufReader<ChildStdout>, SocketAddr) {
+    let mut server = Command::new(env!("CARGO_BIN_EXE_web-server-third-party"))
+        .arg("127.0.0.1:0")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = BufReader::new(server.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+
+    let address = line
+        .strip_prefix("Listening on ")
+        .and_then(|line| line.strip_suffix(".\n"))
+        .unwrap_or_else(|| panic!("the server did not tell its address: {line:?}"))
+        .parse()
+        .unwrap();
+
+    (server, stdout, address)
+}
+
+fn get(stream: &mut TcpStream, path: &str) {
+    stream
+        .write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+        .unwrap();
+}
+
+#[test]
+fn finishes_the_requests_in_flight_on_sigterm() {
+    let (mut server, mut stdout, address) = start_server();
+
+    // answered first, so the server is surely accepting connections
+    let mut stream = TcpStream::connect(address).unwrap();
+    get(&mut stream, "/");
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 200 OK"),
+        "the server did not answer the first request"
+    );
+
+    let mut stream = TcpStream::connect(address).unwrap();
+    get(&mut stream, "/sleep");
+
+    thread::sleep(Duration::from_millis(500));
+
+    let kill_status = Command::new("kill")
+        .args(["-TERM", &server.id().to_string()])
+        .status()
+        .unwrap();
+
+    assert!(kill_status.success(), "the signal was not sent");
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(
+        response.starts_with("HTTP/1.1 200 OK"),
+        "the request in flight was not answered"
+    );
+
+    let status = server.wait().unwrap();
+
+    let mut output = String::new();
+    stdout.read_to_string(&mut output).unwrap();
+
+    assert!(status.success(), "the server exited with a failure");
+    assert_eq!(output, "Shutting down.\n", "the shutdown was not logged");
+    assert!(
+        TcpStream::connect(address).is_err(),
+        "the server still accepts connections"
+    );
+}